@@ -0,0 +1,484 @@
+pub mod pipeline;
+pub mod state;
+pub mod streams;
+pub mod subshell;
+
+use std::io::{self, Write};
+use std::mem;
+use std::process;
+
+use crate::BUILTIN_COMMANDS;
+use crate::executor::pipeline::pipe_command;
+use crate::executor::state::{Jump, ShellState, Variable};
+use crate::executor::streams::Streams;
+use crate::executor::subshell::{fork_shell, wait_for};
+use crate::expansion::pattern::pattern_matches;
+use crate::expansion::{
+    ExpansionError, expand_arithmetic, expand_assignment_value, expand_pattern,
+    expand_word_to_string, expand_words,
+};
+use crate::parser::ast::{
+    AndOrList, ArithmeticForCommand, CaseCommand, CaseItem, CaseTerminator, Command,
+    CompoundCommand, ForCommand, IfCommand, ListOperator, Pipeline, Program, Redirection,
+    SimpleCommand, WhileCommand,
+};
+use crate::shell::{
+    alias::{alias_command, unalias_command},
+    break_continue::{break_command, continue_command},
+    c_type::type_command,
+    cd::cd_command,
+    diff_command::not_shell_builtin_command,
+    echo::echo_command,
+    exit::exit_command,
+    function::{local_command, return_command},
+    history::history_command,
+    pwd::pwd_command,
+    read::read_command,
+};
+use crate::utilities::redirect::{
+    Redirect, RedirectTarget, redirect_fds, remove_here_documents, write_here_document,
+};
+
+pub fn execute_program(state: &mut ShellState, program: &Program) -> i32 {
+    for list in &program.lists {
+        //A `break` or `continue` skips the rest of the loop body.
+        if state.jump.is_some() {
+            break;
+        }
+        execute_and_or(state, list);
+    }
+    state.last_status
+}
+
+fn execute_and_or(state: &mut ShellState, list: &AndOrList) {
+    state.last_status = execute_pipeline(state, &list.first);
+    for (operator, pipeline) in &list.rest {
+        if state.jump.is_some() {
+            return;
+        }
+        let should_run = match operator {
+            ListOperator::And => state.last_status == 0,
+            ListOperator::Or => state.last_status != 0,
+        };
+        if should_run {
+            state.last_status = execute_pipeline(state, pipeline);
+        }
+    }
+}
+
+fn execute_pipeline(state: &mut ShellState, pipeline: &Pipeline) -> i32 {
+    let status = match pipeline.commands.as_slice() {
+        [command] => execute_command(state, command),
+        _ => pipe_command(state, pipeline),
+    };
+    if pipeline.negated {
+        return i32::from(status == 0);
+    }
+    status
+}
+
+pub fn execute_command(state: &mut ShellState, command: &Command) -> i32 {
+    match command {
+        Command::Simple(command) => execute_simple_command(state, command),
+        Command::Compound(compound, redirections) => {
+            execute_compound_command(state, compound, redirections)
+        }
+        Command::Function(definition) => {
+            let body = definition.body.clone();
+            state.functions.insert(definition.name.clone(), body);
+            0
+        }
+    }
+}
+
+//Deep enough for real recursion. Runaway recursion stops here with an error, well within the
+//stack the shell thread is given in main.rs.
+const MAX_FUNCTION_DEPTH: usize = 1000;
+
+//Runs a function body with `args` as `$1`, `$2`, ... and a fresh frame for `local` variables.
+fn call_function(
+    state: &mut ShellState,
+    name: &str,
+    body: &Command,
+    args: &[String],
+    redirections: &[RedirectTarget],
+) -> i32 {
+    if state.locals.len() >= MAX_FUNCTION_DEPTH {
+        eprintln!(
+            "{}: maximum function nesting level exceeded ({})",
+            name, MAX_FUNCTION_DEPTH
+        );
+        return 1;
+    }
+    let saved = match redirect_fds(redirections) {
+        Ok(saved) => saved,
+        Err(err) => {
+            eprintln!("myshell: {}", err);
+            return 1;
+        }
+    };
+    let positional = mem::replace(&mut state.positional, args.to_vec());
+    //`break` inside the function cannot reach a loop in the caller.
+    let loop_depth = mem::take(&mut state.loop_depth);
+    state.locals.push(Vec::new());
+
+    let mut status = execute_command(state, body);
+    if let Some(Jump::Return(returned)) = state.jump {
+        state.jump = None;
+        status = returned;
+    }
+
+    for (name, variable) in state.locals.pop().unwrap_or_default().into_iter().rev() {
+        state.replace_var(&name, variable);
+    }
+    state.loop_depth = loop_depth;
+    state.positional = positional;
+    saved.restore();
+    status
+}
+
+//Redirections of a compound command apply to everything run inside it.
+fn execute_compound_command(
+    state: &mut ShellState,
+    compound: &CompoundCommand,
+    redirections: &[Redirection],
+) -> i32 {
+    let redirections = match expand_redirections(state, redirections) {
+        Ok(redirections) => redirections,
+        Err(err) => return expansion_failed(state, &err),
+    };
+    let saved = match redirect_fds(&redirections) {
+        Ok(saved) => saved,
+        Err(err) => {
+            eprintln!("myshell: {}", err);
+            remove_here_documents(&redirections);
+            return 1;
+        }
+    };
+    let status = match compound {
+        //A forked copy of the shell, so `cd` or assignments inside never reach this one.
+        CompoundCommand::Subshell(body) => match fork_shell(|| execute_program(state, body)) {
+            Ok(pid) => wait_for(pid),
+            Err(err) => {
+                eprintln!("fork: {}", err);
+                1
+            }
+        },
+        CompoundCommand::BraceGroup(body) => execute_program(state, body),
+        CompoundCommand::If(command) => execute_if(state, command),
+        CompoundCommand::While(command) => run_loop(state, |state| execute_while(state, command)),
+        CompoundCommand::For(command) => run_loop(state, |state| execute_for(state, command)),
+        CompoundCommand::ArithmeticFor(command) => {
+            run_loop(state, |state| execute_arithmetic_for(state, command))
+        }
+        CompoundCommand::Case(command) => execute_case(state, command),
+    };
+    saved.restore();
+    remove_here_documents(&redirections);
+    status
+}
+
+fn execute_if(state: &mut ShellState, command: &IfCommand) -> i32 {
+    for (condition, body) in &command.branches {
+        if execute_program(state, condition) == 0 {
+            return execute_program(state, body);
+        }
+    }
+    match &command.else_body {
+        Some(body) => execute_program(state, body),
+        None => 0,
+    }
+}
+
+fn execute_case(state: &mut ShellState, command: &CaseCommand) -> i32 {
+    match case_body(state, command) {
+        Ok(status) => status,
+        Err(err) => expansion_failed(state, &err),
+    }
+}
+
+fn case_body(state: &mut ShellState, command: &CaseCommand) -> Result<i32, ExpansionError> {
+    let word = expand_word_to_string(state, &command.word)?;
+    let mut status = 0;
+    let mut falling_through = false;
+    for item in &command.items {
+        if !falling_through && !case_item_matches(state, item, &word)? {
+            continue;
+        }
+        status = execute_program(state, &item.body);
+        if state.jump.is_some() {
+            break;
+        }
+        match item.terminator {
+            CaseTerminator::Break => break,
+            CaseTerminator::FallThrough => falling_through = true,
+            CaseTerminator::Continue => falling_through = false,
+        }
+    }
+    Ok(status)
+}
+
+fn case_item_matches(
+    state: &mut ShellState,
+    item: &CaseItem,
+    word: &str,
+) -> Result<bool, ExpansionError> {
+    for pattern in &item.patterns {
+        if pattern_matches(&expand_pattern(state, pattern)?, word) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+fn run_loop(state: &mut ShellState, body: impl FnOnce(&mut ShellState) -> i32) -> i32 {
+    state.loop_depth += 1;
+    let status = body(state);
+    state.loop_depth -= 1;
+    status
+}
+
+//Checked after every pass through a loop body: whether the loop goes on.
+fn loop_continues(state: &mut ShellState) -> bool {
+    match state.jump.take() {
+        None | Some(Jump::Continue(1)) => true,
+        Some(Jump::Break(1)) => false,
+        //A `return` leaves every loop in the function.
+        Some(Jump::Return(status)) => {
+            state.jump = Some(Jump::Return(status));
+            false
+        }
+        //Leaves this loop with one level fewer to unwind.
+        Some(Jump::Break(levels)) => {
+            state.jump = Some(Jump::Break(levels - 1));
+            false
+        }
+        Some(Jump::Continue(levels)) => {
+            state.jump = Some(Jump::Continue(levels - 1));
+            false
+        }
+    }
+}
+
+fn execute_while(state: &mut ShellState, command: &WhileCommand) -> i32 {
+    let mut status = 0;
+    loop {
+        let succeeded = execute_program(state, &command.condition) == 0;
+        if !loop_continues(state) {
+            break;
+        }
+        if succeeded == command.until {
+            break;
+        }
+        status = execute_program(state, &command.body);
+        if !loop_continues(state) {
+            break;
+        }
+    }
+    status
+}
+
+fn execute_for(state: &mut ShellState, command: &ForCommand) -> i32 {
+    let values = match &command.words {
+        Some(words) => match expand_words(state, words) {
+            Ok(values) => values,
+            Err(err) => return expansion_failed(state, &err),
+        },
+        None => state.positional.clone(),
+    };
+    let mut status = 0;
+    for value in values {
+        state.set_var(&command.name, value);
+        status = execute_program(state, &command.body);
+        if !loop_continues(state) {
+            break;
+        }
+    }
+    status
+}
+
+fn execute_arithmetic_for(state: &mut ShellState, command: &ArithmeticForCommand) -> i32 {
+    match arithmetic_for_loop(state, command) {
+        Ok(status) => status,
+        Err(err) => expansion_failed(state, &err),
+    }
+}
+
+fn arithmetic_for_loop(
+    state: &mut ShellState,
+    command: &ArithmeticForCommand,
+) -> Result<i32, ExpansionError> {
+    let mut status = 0;
+    expand_arithmetic(state, &command.init)?;
+    loop {
+        let always = command.condition.to_string().trim().is_empty();
+        if !always && expand_arithmetic(state, &command.condition)? == 0 {
+            return Ok(status);
+        }
+        status = execute_program(state, &command.body);
+        if !loop_continues(state) {
+            return Ok(status);
+        }
+        expand_arithmetic(state, &command.step)?;
+    }
+}
+
+fn expand_redirections(
+    state: &mut ShellState,
+    redirections: &[Redirection],
+) -> Result<Vec<RedirectTarget>, ExpansionError> {
+    let mut expanded = Vec::new();
+    for redirection in redirections {
+        match expand_redirection(state, redirection) {
+            Ok(target) => expanded.push(target),
+            Err(err) => {
+                //Here-documents written for the redirections before this one are not needed now.
+                remove_here_documents(&expanded);
+                return Err(err);
+            }
+        }
+    }
+    Ok(expanded)
+}
+
+fn expand_redirection(
+    state: &mut ShellState,
+    redirection: &Redirection,
+) -> Result<RedirectTarget, ExpansionError> {
+    let mut target = expand_word_to_string(state, &redirection.target)?;
+    if redirection.kind == Redirect::HereString {
+        target.push('\n');
+    }
+    if redirection.kind.is_here_document() {
+        target = write_here_document(&target).map_err(ExpansionError::HereDocument)?;
+    }
+    Ok(RedirectTarget {
+        kind: redirection.kind,
+        filepath: target,
+    })
+}
+
+fn command_assignments(
+    state: &mut ShellState,
+    command: &SimpleCommand,
+) -> Result<Vec<(String, String)>, ExpansionError> {
+    let mut assignments = Vec::new();
+    for assignment in &command.assignments {
+        let value = expand_assignment_value(state, &assignment.value)?;
+        assignments.push((assignment.name.clone(), value));
+    }
+    Ok(assignments)
+}
+
+//Reports an expansion error. `${name:?message}` also ends a shell that is not interactive, so a
+//script stops there the way it does in bash.
+fn expansion_failed(state: &ShellState, err: &ExpansionError) -> i32 {
+    eprintln!("{}", err);
+    if matches!(err, ExpansionError::ParameterUnset(..)) && !state.interactive {
+        let _ = io::stdout().flush();
+        process::exit(1);
+    }
+    1
+}
+
+fn execute_simple_command(state: &mut ShellState, command: &SimpleCommand) -> i32 {
+    state.substitution_status = None;
+    let expanded = expand_words(state, &command.words)
+        .and_then(|argv| Ok((argv, expand_redirections(state, &command.redirections)?)));
+    let (argv, redirections) = match expanded {
+        Ok(expanded) => expanded,
+        Err(err) => return expansion_failed(state, &err),
+    };
+    let assignments = match command_assignments(state, command) {
+        Ok(assignments) => assignments,
+        Err(err) => {
+            remove_here_documents(&redirections);
+            return expansion_failed(state, &err);
+        }
+    };
+    if argv.is_empty() {
+        //With no command the files are still opened, so `> file` creates or truncates it.
+        let opened = redirect_fds(&redirections);
+        remove_here_documents(&redirections);
+        match opened {
+            Ok(saved) => saved.restore(),
+            Err(err) => {
+                eprintln!("myshell: {}", err);
+                return 1;
+            }
+        }
+        for (name, value) in assignments {
+            state.set_var(&name, value);
+        }
+        //`x=$(cmd)` reports how cmd went.
+        return state.substitution_status.unwrap_or(0);
+    }
+
+    //`NAME=value cmd` only puts NAME in the environment of that one command.
+    let mut previous = Vec::new();
+    for (name, value) in assignments {
+        let variable = Variable {
+            value,
+            exported: true,
+        };
+        let old = state.replace_var(&name, Some(variable));
+        previous.push((name, old));
+    }
+    let status = run_command(state, &argv, &redirections);
+    remove_here_documents(&redirections);
+    for (name, variable) in previous.into_iter().rev() {
+        state.replace_var(&name, variable);
+    }
+    status
+}
+
+//Builtins first, then functions, then programs on PATH.
+fn run_command(state: &mut ShellState, argv: &[String], redirections: &[RedirectTarget]) -> i32 {
+    let (program, args) = argv.split_first().expect("argv always has a program name");
+    if !BUILTIN_COMMANDS.contains(&program.as_str()) {
+        return match state.functions.get(program).cloned() {
+            Some(body) => call_function(state, program, &body, args, redirections),
+            None => not_shell_builtin_command(state, argv, redirections),
+        };
+    }
+    //Builtins write through the streams set up here instead of the shell's own fds, so nothing
+    //has to be put back once they return.
+    let mut streams = match Streams::redirected(redirections) {
+        Ok(streams) => streams,
+        Err(err) => {
+            eprintln!("myshell: {}", err);
+            return 1;
+        }
+    };
+    match run_builtin(state, program, args, &mut streams) {
+        Ok(status) => status,
+        Err(err) => {
+            let _ = writeln!(streams.stderr(), "{}: write error: {}", program, err);
+            1
+        }
+    }
+}
+
+fn run_builtin(
+    state: &mut ShellState,
+    program: &str,
+    args: &[String],
+    streams: &mut Streams,
+) -> io::Result<i32> {
+    match program {
+        "exit" => exit_command(state, args),
+        "echo" => echo_command(args, streams),
+        "type" => type_command(state, args, streams),
+        "pwd" => pwd_command(streams),
+        "cd" => cd_command(state, args, streams),
+        "history" => history_command(args, streams),
+        "break" => break_command(state, args, streams),
+        "continue" => continue_command(state, args, streams),
+        "read" => read_command(state, args, streams),
+        "local" => local_command(state, args, streams),
+        "return" => return_command(state, args, streams),
+        "alias" => alias_command(state, args, streams),
+        "unalias" => unalias_command(state, args, streams),
+        _ => unreachable!("{} is listed in BUILTIN_COMMANDS", program),
+    }
+}
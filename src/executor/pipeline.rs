@@ -0,0 +1,74 @@
+use std::io::{self, PipeReader, PipeWriter};
+use std::os::fd::AsRawFd;
+
+use crate::executor::execute_command;
+use crate::executor::state::ShellState;
+use crate::executor::subshell::{fork_shell, wait_for};
+use crate::parser::ast::Pipeline;
+
+pub fn pipe_command(state: &mut ShellState, pipeline: &Pipeline) -> i32 {
+    let mut previous_stdout: Option<PipeReader> = None;
+    let mut running = Vec::new();
+    let mut started_all = true;
+    let last_stage = pipeline.commands.len() - 1;
+    for (stage, command) in pipeline.commands.iter().enumerate() {
+        let (next_stdin, stdout) = if stage == last_stage {
+            (None, None)
+        } else {
+            match io::pipe() {
+                Ok((reader, writer)) => (Some(reader), Some(writer)),
+                Err(err) => {
+                    eprintln!("pipe: {}", err);
+                    started_all = false;
+                    break;
+                }
+            }
+        };
+        let stdin = previous_stdout.take();
+        previous_stdout = next_stdin;
+
+        //Every stage runs in a forked copy of the shell, which expands it and applies its own
+        //redirections on top of the pipe ends just like outside a pipeline. A stage that fails
+        //to expand only fails itself, and builtins like `cd` or `exit` leave the shell alone.
+        let forked = fork_stage(stdin.as_ref(), stdout.as_ref(), || {
+            //The read end of the next pipe belongs to the next stage. Held open here as well, a
+            //writer like `yes` would never see its reader go away.
+            drop(previous_stdout.take());
+            execute_command(state, command)
+        });
+        match forked {
+            Ok(pid) => running.push(pid),
+            Err(err) => {
+                eprintln!("fork: {}", err);
+                started_all = false;
+                break;
+            }
+        }
+    }
+
+    //Stages already running are waited for even when a later one could not be started.
+    let mut status = 1;
+    for pid in running {
+        status = wait_for(pid);
+    }
+    if started_all { status } else { 1 }
+}
+
+//Forks a copy of the shell for one stage, with the pipe ends as its stdin and stdout.
+fn fork_stage(
+    stdin: Option<&PipeReader>,
+    stdout: Option<&PipeWriter>,
+    body: impl FnOnce() -> i32,
+) -> io::Result<libc::pid_t> {
+    fork_shell(|| {
+        unsafe {
+            if let Some(stdin) = stdin {
+                libc::dup2(stdin.as_raw_fd(), 0);
+            }
+            if let Some(stdout) = stdout {
+                libc::dup2(stdout.as_raw_fd(), 1);
+            }
+        }
+        body()
+    })
+}
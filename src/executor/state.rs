@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+use std::env;
+use std::rc::Rc;
+
+use crate::parser::ast::Command;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Variable {
+    pub value: String,
+    pub exported: bool,
+}
+
+//A pending `break N` or `continue N`, unwound one loop level at a time, or a `return N` that
+//unwinds the whole function call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Jump {
+    Break(usize),
+    Continue(usize),
+    Return(i32),
+}
+
+//Variables saved by `local` in one function call, put back when it returns.
+pub type LocalFrame = Vec<(String, Option<Variable>)>;
+
+//Everything the shell remembers between commands.
+pub struct ShellState {
+    variables: HashMap<String, Variable>,
+    pub last_status: i32,
+    pub shell_name: String,
+    pub positional: Vec<String>,
+    //Status of the last command substitution run while expanding the current command.
+    pub substitution_status: Option<i32>,
+    //How many loops are running, and whether one of them was asked to stop or skip ahead.
+    pub loop_depth: usize,
+    pub jump: Option<Jump>,
+    pub functions: HashMap<String, Rc<Command>>,
+    pub aliases: HashMap<String, String>,
+    //One frame per function call that is running, innermost last.
+    pub locals: Vec<LocalFrame>,
+    //The shell's own pid, which `$$` gives everywhere. Forked copies running subshells and
+    //pipeline stages keep it, so they can tell they are not the shell itself.
+    pub shell_pid: u32,
+    //Reading commands from the terminal rather than running a script.
+    pub interactive: bool,
+}
+
+impl ShellState {
+    pub fn new() -> Self {
+        let variables = env::vars()
+            .map(|(name, value)| {
+                let variable = Variable {
+                    value,
+                    exported: true,
+                };
+                (name, variable)
+            })
+            .collect();
+        let mut state = ShellState {
+            variables,
+            last_status: 0,
+            shell_name: env::args().next().unwrap_or_default(),
+            positional: Vec::new(),
+            substitution_status: None,
+            loop_depth: 0,
+            jump: None,
+            functions: HashMap::new(),
+            aliases: HashMap::new(),
+            locals: Vec::new(),
+            shell_pid: std::process::id(),
+            interactive: false,
+        };
+        if let Ok(current_path) = env::current_dir() {
+            state.set_var("PWD", current_path.display().to_string());
+        }
+        state
+    }
+
+    pub fn get_var(&self, name: &str) -> Option<&str> {
+        self.variables.get(name).map(|var| var.value.as_str())
+    }
+
+    //Keeps the exported flag of an existing variable.
+    pub fn set_var(&mut self, name: &str, value: String) {
+        match self.variables.get_mut(name) {
+            Some(variable) => variable.value = value,
+            None => {
+                let variable = Variable {
+                    value,
+                    exported: false,
+                };
+                self.variables.insert(name.to_string(), variable);
+            }
+        }
+    }
+
+    //Swaps a variable wholesale and hands back whatever was there, for `NAME=value cmd`.
+    pub fn replace_var(&mut self, name: &str, variable: Option<Variable>) -> Option<Variable> {
+        match variable {
+            Some(variable) => self.variables.insert(name.to_string(), variable),
+            None => self.variables.remove(name),
+        }
+    }
+
+    //Makes `name` local to the innermost function call, unset until assigned. False outside a function.
+    pub fn declare_local(&mut self, name: &str) -> bool {
+        let Some(frame) = self.locals.last_mut() else {
+            return false;
+        };
+        if !frame.iter().any(|(saved, _)| saved == name) {
+            frame.push((name.to_string(), self.variables.remove(name)));
+        }
+        true
+    }
+
+    //The environment handed to external commands.
+    pub fn exported_vars(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.variables
+            .iter()
+            .filter(|(_, var)| var.exported)
+            .map(|(name, var)| (name.as_str(), var.value.as_str()))
+    }
+
+    //Special and positional parameters first, then ordinary variables.
+    pub fn lookup_parameter(&self, name: &str) -> Option<String> {
+        match name {
+            "?" => Some(self.last_status.to_string()),
+            "$" => Some(self.shell_pid.to_string()),
+            "#" => Some(self.positional.len().to_string()),
+            "0" => Some(self.shell_name.clone()),
+            "@" => Some(self.positional.join(" ")),
+            //`"$*"` joins the arguments with the first character of IFS.
+            "*" => {
+                let ifs = self.get_var("IFS").unwrap_or(" ");
+                let separator = ifs.chars().next().map(String::from).unwrap_or_default();
+                Some(self.positional.join(&separator))
+            }
+            "-" => Some(String::new()),
+            _ => match name.parse::<usize>() {
+                Ok(index) => index
+                    .checked_sub(1)
+                    .and_then(|index| self.positional.get(index))
+                    .cloned(),
+                Err(_) => self.get_var(name).map(str::to_string),
+            },
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::os::fd::RawFd;
+
+use crate::utilities::redirect::{Redirect, RedirectTarget, duplicate_fd, open_target};
+
+//One fd of a builtin: an open file, or nothing after `>&-`.
+pub enum Stream {
+    Open(File),
+    Closed,
+}
+
+impl Stream {
+    fn try_clone(&self, fd: RawFd) -> io::Result<Stream> {
+        match self {
+            Stream::Open(file) => file.try_clone().map(Stream::Open),
+            Stream::Closed => Err(bad_fd(fd)),
+        }
+    }
+}
+
+fn bad_fd(fd: RawFd) -> io::Error {
+    let err = io::Error::from_raw_os_error(libc::EBADF);
+    io::Error::new(err.kind(), format!("{}: {}", fd, err))
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Stream::Open(file) => file.read(buf),
+            Stream::Closed => Err(io::Error::from_raw_os_error(libc::EBADF)),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Stream::Open(file) => file.write(buf),
+            Stream::Closed => Err(io::Error::from_raw_os_error(libc::EBADF)),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Stream::Open(file) => file.flush(),
+            Stream::Closed => Ok(()),
+        }
+    }
+}
+
+//The fds a builtin reads and writes. They start as copies of the shell's own, and redirections
+//replace the copies, so the shell's fds stay as they are whatever the builtin was given.
+pub struct Streams {
+    fds: HashMap<RawFd, Stream>,
+}
+
+impl Streams {
+    //Copies of the shell's stdin, stdout and stderr.
+    pub fn inherited() -> Streams {
+        let fds = (0..=2)
+            .map(|fd| match duplicate_fd(&fd.to_string()) {
+                Ok(file) => (fd, Stream::Open(file)),
+                Err(_) => (fd, Stream::Closed),
+            })
+            .collect();
+        Streams { fds }
+    }
+
+    //The shell's streams with `redirections` applied on top.
+    pub fn redirected(redirections: &[RedirectTarget]) -> io::Result<Streams> {
+        let mut streams = Streams::inherited();
+        streams.redirect(redirections)?;
+        Ok(streams)
+    }
+
+    //Applies redirections left to right, the same way `redirect_fds` does to real fds.
+    pub fn redirect(&mut self, redirections: &[RedirectTarget]) -> io::Result<()> {
+        for redirection in redirections {
+            let fd = redirection.kind.fd();
+            match redirection.kind {
+                Redirect::DuplicateOutput(_) | Redirect::DuplicateInput(_)
+                    if redirection.filepath == "-" =>
+                {
+                    self.fds.insert(fd, Stream::Closed);
+                }
+                Redirect::DuplicateOutput(_) | Redirect::DuplicateInput(_) => {
+                    let source = self.duplicate(&redirection.filepath)?;
+                    self.fds.insert(fd, source);
+                }
+                Redirect::OutputAll | Redirect::AppendAll => {
+                    let file = open_target(redirection)?;
+                    self.fds.insert(1, Stream::Open(file.try_clone()?));
+                    self.fds.insert(2, Stream::Open(file));
+                }
+                _ => {
+                    let file = open_target(redirection)?;
+                    self.fds.insert(fd, Stream::Open(file));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    //A copy of the fd named by the target of `>&` or `<&`, which may itself be redirected.
+    fn duplicate(&self, target: &str) -> io::Result<Stream> {
+        if let Ok(fd) = target.parse::<RawFd>()
+            && let Some(stream) = self.fds.get(&fd)
+        {
+            return stream.try_clone(fd);
+        }
+        duplicate_fd(target)
+            .map(Stream::Open)
+            .map_err(|err| io::Error::new(err.kind(), format!("{}: {}", target, err)))
+    }
+
+    pub fn stdin(&mut self) -> &mut Stream {
+        self.fds.entry(0).or_insert(Stream::Closed)
+    }
+
+    pub fn stdout(&mut self) -> &mut Stream {
+        self.fds.entry(1).or_insert(Stream::Closed)
+    }
+
+    pub fn stderr(&mut self) -> &mut Stream {
+        self.fds.entry(2).or_insert(Stream::Closed)
+    }
+}
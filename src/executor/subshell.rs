@@ -0,0 +1,37 @@
+use std::io::{self, Write};
+use std::os::unix::process::ExitStatusExt;
+use std::process::ExitStatus;
+
+use crate::utilities::exit_status::exit_code;
+
+//Forks a copy of the shell that runs `body` and exits with its status. The parent gets the child's pid.
+pub fn fork_shell(body: impl FnOnce() -> i32) -> io::Result<libc::pid_t> {
+    //Anything still buffered would otherwise be written twice, once by each process.
+    io::stdout().flush()?;
+    io::stderr().flush()?;
+    match unsafe { libc::fork() } {
+        -1 => Err(io::Error::last_os_error()),
+        0 => {
+            //Rust ignores SIGPIPE in the shell. A copy that writes into a pipe should die like
+            //any program once its reader has gone, instead of failing every write.
+            unsafe { libc::signal(libc::SIGPIPE, libc::SIG_DFL) };
+            let status = body();
+            let _ = io::stdout().flush();
+            let _ = io::stderr().flush();
+            unsafe { libc::_exit(status) }
+        }
+        pid => Ok(pid),
+    }
+}
+
+pub fn wait_for(pid: libc::pid_t) -> i32 {
+    let mut status = 0;
+    loop {
+        if unsafe { libc::waitpid(pid, &mut status, 0) } != -1 {
+            return exit_code(ExitStatus::from_raw(status));
+        }
+        if io::Error::last_os_error().kind() != io::ErrorKind::Interrupted {
+            return 1;
+        }
+    }
+}
@@ -0,0 +1,512 @@
+use std::iter::Peekable;
+use std::str::Chars;
+
+use thiserror::Error;
+
+use crate::executor::state::ShellState;
+
+//Variables holding expressions are evaluated in turn, so `a=b b=a` must not loop forever.
+const MAX_VARIABLE_DEPTH: usize = 64;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ArithmeticError {
+    #[error("division by 0 (error token is \"{0}\")")]
+    DivisionByZero(String),
+    #[error("exponent less than 0 (error token is \"{0}\")")]
+    NegativeExponent(String),
+    #[error("syntax error: operand expected (error token is \"{0}\")")]
+    OperandExpected(String),
+    #[error("syntax error in expression (error token is \"{0}\")")]
+    UnexpectedToken(String),
+    #[error("value too great for base (error token is \"{0}\")")]
+    InvalidNumber(String),
+    #[error("attempted assignment to non-variable (error token is \"{0}\")")]
+    NotAVariable(String),
+    #[error("expression recursion level exceeded (error token is \"{0}\")")]
+    TooDeep(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ArithToken {
+    Number(String),
+    Name(String),
+    Op(&'static str),
+}
+
+impl ArithToken {
+    fn text(&self) -> &str {
+        match self {
+            ArithToken::Number(text) | ArithToken::Name(text) => text,
+            ArithToken::Op(op) => op,
+        }
+    }
+}
+
+//Longest operators first so `<<=` wins over `<<` and `<`.
+const OPERATORS: &[&str] = &[
+    "<<=", ">>=", "**", "++", "--", "<<", ">>", "<=", ">=", "==", "!=", "&&", "||", "*=", "/=",
+    "%=", "+=", "-=", "&=", "^=", "|=", "+", "-", "*", "/", "%", "<", ">", "&", "^", "|", "!", "~",
+    "?", ":", "=", "(", ")", ",",
+];
+
+fn tokenize(expression: &str) -> Result<Vec<ArithToken>, ArithmeticError> {
+    let mut tokens = Vec::new();
+    let mut chars: Peekable<Chars> = expression.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_digit() {
+            let mut number = String::new();
+            while let Some(c) =
+                chars.next_if(|c| c.is_ascii_alphanumeric() || *c == '#' || *c == '_' || *c == '@')
+            {
+                number.push(c);
+            }
+            tokens.push(ArithToken::Number(number));
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let mut name = String::new();
+            while let Some(c) = chars.next_if(|c| c.is_ascii_alphanumeric() || *c == '_') {
+                name.push(c);
+            }
+            tokens.push(ArithToken::Name(name));
+        } else {
+            let rest: String = chars.clone().take(3).collect();
+            let Some(op) = OPERATORS.iter().find(|op| rest.starts_with(**op)) else {
+                return Err(ArithmeticError::UnexpectedToken(chars.collect()));
+            };
+            for _ in 0..op.len() {
+                chars.next();
+            }
+            tokens.push(ArithToken::Op(op));
+        }
+    }
+    Ok(tokens)
+}
+
+//Decimal, 0x hex, leading-0 octal and `base#digits` for bases 2 to 64.
+fn parse_number(text: &str) -> Result<i64, ArithmeticError> {
+    let invalid = || ArithmeticError::InvalidNumber(text.to_string());
+    let (base, digits) = if let Some((base, digits)) = text.split_once('#') {
+        let base: u32 = base.parse().map_err(|_| invalid())?;
+        if !(2..=64).contains(&base) {
+            return Err(invalid());
+        }
+        (base, digits)
+    } else if let Some(hex) = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        (16, hex)
+    } else if text.len() > 1 && text.starts_with('0') {
+        (8, &text[1..])
+    } else {
+        (10, text)
+    };
+    if digits.is_empty() {
+        return Err(invalid());
+    }
+    let mut value: i64 = 0;
+    for c in digits.chars() {
+        let digit = match c {
+            '0'..='9' => c as u32 - '0' as u32,
+            'a'..='z' => c as u32 - 'a' as u32 + 10,
+            'A'..='Z' if base <= 36 => c as u32 - 'A' as u32 + 10,
+            'A'..='Z' => c as u32 - 'A' as u32 + 36,
+            '@' => 62,
+            '_' => 63,
+            _ => return Err(invalid()),
+        };
+        if digit >= base {
+            return Err(invalid());
+        }
+        value = value.wrapping_mul(base as i64).wrapping_add(digit as i64);
+    }
+    Ok(value)
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Number(i64),
+    Variable(String),
+    Unary(&'static str, Box<Expr>),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+    Conditional(Box<Expr>, Box<Expr>, Box<Expr>),
+    //`x = e`, `x += e` and so on. The operator is "" for plain assignment.
+    Assign(String, &'static str, Box<Expr>),
+    PreIncrement(String, i64),
+    PostIncrement(String, i64),
+}
+
+struct ArithParser {
+    tokens: Vec<ArithToken>,
+    position: usize,
+}
+
+//Binary operators from loosest to tightest binding. `**` is handled separately as it is right associative.
+const BINARY_LEVELS: &[&[&str]] = &[
+    &["||"],
+    &["&&"],
+    &["|"],
+    &["^"],
+    &["&"],
+    &["==", "!="],
+    &["<=", ">=", "<", ">"],
+    &["<<", ">>"],
+    &["+", "-"],
+    &["*", "/", "%"],
+];
+
+impl ArithParser {
+    fn peek_op(&self) -> Option<&'static str> {
+        match self.tokens.get(self.position) {
+            Some(ArithToken::Op(op)) => Some(op),
+            _ => None,
+        }
+    }
+
+    fn error_token(&self) -> String {
+        self.tokens[self.position.min(self.tokens.len())..]
+            .iter()
+            .map(ArithToken::text)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn expect(&mut self, op: &str) -> Result<(), ArithmeticError> {
+        if self.peek_op() == Some(op) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(ArithmeticError::UnexpectedToken(self.error_token()))
+        }
+    }
+
+    fn parse_comma(&mut self) -> Result<Expr, ArithmeticError> {
+        let mut expr = self.parse_assignment()?;
+        while self.peek_op() == Some(",") {
+            self.position += 1;
+            let right = self.parse_assignment()?;
+            expr = Expr::Binary(",", Box::new(expr), Box::new(right));
+        }
+        Ok(expr)
+    }
+
+    fn parse_assignment(&mut self) -> Result<Expr, ArithmeticError> {
+        let start = self.position;
+        let left = self.parse_conditional()?;
+        let Some(op) = self.peek_op() else {
+            return Ok(left);
+        };
+        let compound = match op {
+            "=" => "",
+            "*=" | "/=" | "%=" | "+=" | "-=" | "<<=" | ">>=" | "&=" | "^=" | "|=" => {
+                &op[..op.len() - 1]
+            }
+            _ => return Ok(left),
+        };
+        let Expr::Variable(name) = left else {
+            self.position = start;
+            return Err(ArithmeticError::NotAVariable(self.error_token()));
+        };
+        self.position += 1;
+        let value = self.parse_assignment()?;
+        Ok(Expr::Assign(name, compound, Box::new(value)))
+    }
+
+    fn parse_conditional(&mut self) -> Result<Expr, ArithmeticError> {
+        let condition = self.parse_binary(0)?;
+        if self.peek_op() != Some("?") {
+            return Ok(condition);
+        }
+        self.position += 1;
+        let when_true = self.parse_comma()?;
+        self.expect(":")?;
+        let when_false = self.parse_conditional()?;
+        Ok(Expr::Conditional(
+            Box::new(condition),
+            Box::new(when_true),
+            Box::new(when_false),
+        ))
+    }
+
+    fn parse_binary(&mut self, level: usize) -> Result<Expr, ArithmeticError> {
+        if level == BINARY_LEVELS.len() {
+            return self.parse_power();
+        }
+        let mut left = self.parse_binary(level + 1)?;
+        while let Some(op) = self
+            .peek_op()
+            .filter(|op| BINARY_LEVELS[level].contains(op))
+        {
+            self.position += 1;
+            let right = self.parse_binary(level + 1)?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_power(&mut self) -> Result<Expr, ArithmeticError> {
+        let base = self.parse_unary()?;
+        if self.peek_op() != Some("**") {
+            return Ok(base);
+        }
+        self.position += 1;
+        let exponent = self.parse_power()?;
+        Ok(Expr::Binary("**", Box::new(base), Box::new(exponent)))
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, ArithmeticError> {
+        match self.peek_op() {
+            Some(op @ ("++" | "--")) => {
+                self.position += 1;
+                let Some(ArithToken::Name(name)) = self.tokens.get(self.position).cloned() else {
+                    return Err(ArithmeticError::NotAVariable(self.error_token()));
+                };
+                self.position += 1;
+                Ok(Expr::PreIncrement(name, if op == "++" { 1 } else { -1 }))
+            }
+            Some(op @ ("-" | "+" | "!" | "~")) => {
+                self.position += 1;
+                Ok(Expr::Unary(op, Box::new(self.parse_unary()?)))
+            }
+            _ => self.parse_postfix(),
+        }
+    }
+
+    fn parse_postfix(&mut self) -> Result<Expr, ArithmeticError> {
+        let primary = self.parse_primary()?;
+        if let Expr::Variable(name) = &primary
+            && let Some(op @ ("++" | "--")) = self.peek_op()
+        {
+            self.position += 1;
+            return Ok(Expr::PostIncrement(
+                name.clone(),
+                if op == "++" { 1 } else { -1 },
+            ));
+        }
+        Ok(primary)
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, ArithmeticError> {
+        let Some(token) = self.tokens.get(self.position).cloned() else {
+            return Err(ArithmeticError::OperandExpected(String::new()));
+        };
+        match token {
+            ArithToken::Number(text) => {
+                self.position += 1;
+                Ok(Expr::Number(parse_number(&text)?))
+            }
+            ArithToken::Name(name) => {
+                self.position += 1;
+                Ok(Expr::Variable(name))
+            }
+            ArithToken::Op("(") => {
+                self.position += 1;
+                let inner = self.parse_comma()?;
+                self.expect(")")?;
+                Ok(inner)
+            }
+            ArithToken::Op(_) => Err(ArithmeticError::OperandExpected(self.error_token())),
+        }
+    }
+}
+
+struct Evaluator<'a> {
+    state: &'a mut ShellState,
+    depth: usize,
+}
+
+impl Evaluator<'_> {
+    fn variable(&mut self, name: &str) -> Result<i64, ArithmeticError> {
+        let value = self
+            .state
+            .get_var(name)
+            .unwrap_or_default()
+            .trim()
+            .to_string();
+        if value.is_empty() {
+            return Ok(0);
+        }
+        if let Ok(number) = parse_number(&value) {
+            return Ok(number);
+        }
+        if self.depth >= MAX_VARIABLE_DEPTH {
+            return Err(ArithmeticError::TooDeep(value));
+        }
+        self.depth += 1;
+        let result = self.evaluate_text(&value);
+        self.depth -= 1;
+        result
+    }
+
+    fn evaluate_text(&mut self, expression: &str) -> Result<i64, ArithmeticError> {
+        let tokens = tokenize(expression)?;
+        if tokens.is_empty() {
+            return Ok(0);
+        }
+        let mut parser = ArithParser {
+            tokens,
+            position: 0,
+        };
+        let expr = parser.parse_comma()?;
+        if parser.position < parser.tokens.len() {
+            return Err(ArithmeticError::UnexpectedToken(parser.error_token()));
+        }
+        self.evaluate(&expr)
+    }
+
+    fn assign(&mut self, name: &str, value: i64) -> i64 {
+        self.state.set_var(name, value.to_string());
+        value
+    }
+
+    fn evaluate(&mut self, expr: &Expr) -> Result<i64, ArithmeticError> {
+        match expr {
+            Expr::Number(value) => Ok(*value),
+            Expr::Variable(name) => self.variable(name),
+            Expr::Unary(op, operand) => {
+                let value = self.evaluate(operand)?;
+                Ok(match *op {
+                    "-" => value.wrapping_neg(),
+                    "!" => i64::from(value == 0),
+                    "~" => !value,
+                    _ => value,
+                })
+            }
+            Expr::Binary("&&", left, right) => Ok(i64::from(
+                self.evaluate(left)? != 0 && self.evaluate(right)? != 0,
+            )),
+            Expr::Binary("||", left, right) => Ok(i64::from(
+                self.evaluate(left)? != 0 || self.evaluate(right)? != 0,
+            )),
+            Expr::Binary(op, left, right) => {
+                let left = self.evaluate(left)?;
+                let right = self.evaluate(right)?;
+                apply_binary(op, left, right)
+            }
+            Expr::Conditional(condition, when_true, when_false) => {
+                if self.evaluate(condition)? != 0 {
+                    self.evaluate(when_true)
+                } else {
+                    self.evaluate(when_false)
+                }
+            }
+            Expr::Assign(name, op, value) => {
+                let value = self.evaluate(value)?;
+                let value = if op.is_empty() {
+                    value
+                } else {
+                    apply_binary(op, self.variable(name)?, value)?
+                };
+                Ok(self.assign(name, value))
+            }
+            Expr::PreIncrement(name, delta) => {
+                let value = self.variable(name)?.wrapping_add(*delta);
+                Ok(self.assign(name, value))
+            }
+            Expr::PostIncrement(name, delta) => {
+                let value = self.variable(name)?;
+                self.assign(name, value.wrapping_add(*delta));
+                Ok(value)
+            }
+        }
+    }
+}
+
+fn apply_binary(op: &str, left: i64, right: i64) -> Result<i64, ArithmeticError> {
+    Ok(match op {
+        "," => right,
+        "|" => left | right,
+        "^" => left ^ right,
+        "&" => left & right,
+        "==" => i64::from(left == right),
+        "!=" => i64::from(left != right),
+        "<=" => i64::from(left <= right),
+        ">=" => i64::from(left >= right),
+        "<" => i64::from(left < right),
+        ">" => i64::from(left > right),
+        "<<" => left.wrapping_shl(right as u32),
+        ">>" => left.wrapping_shr(right as u32),
+        "+" => left.wrapping_add(right),
+        "-" => left.wrapping_sub(right),
+        "*" => left.wrapping_mul(right),
+        "/" | "%" if right == 0 => {
+            return Err(ArithmeticError::DivisionByZero(right.to_string()));
+        }
+        "/" => left.wrapping_div(right),
+        "%" => left.wrapping_rem(right),
+        "**" if right < 0 => {
+            return Err(ArithmeticError::NegativeExponent(right.to_string()));
+        }
+        "**" => left.wrapping_pow(right.min(u32::MAX as i64) as u32),
+        _ => unreachable!("unknown arithmetic operator {}", op),
+    })
+}
+
+//Evaluates an already expanded `$((...))` body, updating any variables it assigns to.
+pub fn evaluate_arithmetic(
+    state: &mut ShellState,
+    expression: &str,
+) -> Result<i64, ArithmeticError> {
+    let mut evaluator = Evaluator { state, depth: 0 };
+    evaluator.evaluate_text(expression)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evaluate(expression: &str) -> Result<i64, ArithmeticError> {
+        evaluate_arithmetic(&mut ShellState::new(), expression)
+    }
+
+    #[test]
+    fn operators_follow_c_precedence() {
+        assert_eq!(evaluate("2 + 3 * 4"), Ok(14));
+        assert_eq!(evaluate("(2 + 3) * 4"), Ok(20));
+        assert_eq!(evaluate("10 - 4 - 3"), Ok(3));
+        assert_eq!(evaluate("1 << 2 + 1"), Ok(8));
+        assert_eq!(evaluate("7 & 3 | 8"), Ok(11));
+        assert_eq!(evaluate("1 + 2 == 3"), Ok(1));
+        assert_eq!(evaluate("!0 && 3 > 2 || 0"), Ok(1));
+        assert_eq!(evaluate("0 ? 2 : 3"), Ok(3));
+    }
+
+    #[test]
+    fn exponent_binds_right_and_above_unary_minus() {
+        assert_eq!(evaluate("2 ** 3 ** 2"), Ok(512));
+        assert_eq!(evaluate("-2 ** 2"), Ok(4));
+        assert_eq!(
+            evaluate("2 ** -1"),
+            Err(ArithmeticError::NegativeExponent("-1".to_string()))
+        );
+    }
+
+    #[test]
+    fn division_truncates_toward_zero() {
+        assert_eq!(evaluate("-7 / 2"), Ok(-3));
+        assert_eq!(evaluate("-7 % 2"), Ok(-1));
+    }
+
+    #[test]
+    fn division_and_modulo_by_zero_fail() {
+        let error = Err(ArithmeticError::DivisionByZero("0".to_string()));
+        assert_eq!(evaluate("1 / 0"), error);
+        assert_eq!(evaluate("5 % (2 - 2)"), error);
+        assert_eq!(evaluate("x = 4, x /= 0"), error);
+    }
+
+    #[test]
+    fn overflow_wraps_like_bash() {
+        assert_eq!(evaluate("9223372036854775807 + 1"), Ok(i64::MIN));
+        assert_eq!(evaluate("9223372036854775807 * 2"), Ok(-2));
+        assert_eq!(evaluate("-9223372036854775808 / -1"), Ok(i64::MIN));
+        assert_eq!(evaluate("-9223372036854775808 % -1"), Ok(0));
+    }
+
+    #[test]
+    fn assignments_update_variables() {
+        let mut state = ShellState::new();
+        assert_eq!(evaluate_arithmetic(&mut state, "x = 3, x += 2"), Ok(5));
+        assert_eq!(state.get_var("x"), Some("5"));
+        assert_eq!(evaluate_arithmetic(&mut state, "x++ + ++x"), Ok(12));
+        assert_eq!(state.get_var("x"), Some("7"));
+    }
+}
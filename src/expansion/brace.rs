@@ -0,0 +1,261 @@
+use crate::parser::ast::{Word, WordPart};
+
+//Only unquoted literal characters can form a brace expression. Every other part rides along untouched.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Unit {
+    Char(char),
+    Part(WordPart),
+}
+
+//Expands `{a,b}` lists and `{1..10..2}` sequences, before any other expansion happens.
+pub fn expand_braces(word: &Word) -> Vec<Word> {
+    let mut units = Vec::new();
+    for part in &word.parts {
+        match part {
+            WordPart::Literal(text) => units.extend(text.chars().map(Unit::Char)),
+            part => units.push(Unit::Part(part.clone())),
+        }
+    }
+    if !units.contains(&Unit::Char('{')) {
+        return vec![word.clone()];
+    }
+    expand_units(&units)
+        .into_iter()
+        .map(|units| to_word(&units))
+        .collect()
+}
+
+fn to_word(units: &[Unit]) -> Word {
+    let mut parts = Vec::new();
+    for unit in units {
+        match (unit, parts.last_mut()) {
+            (Unit::Char(c), Some(WordPart::Literal(text))) => text.push(*c),
+            (Unit::Char(c), _) => parts.push(WordPart::Literal(c.to_string())),
+            (Unit::Part(part), _) => parts.push(part.clone()),
+        }
+    }
+    Word { parts }
+}
+
+fn expand_units(units: &[Unit]) -> Vec<Vec<Unit>> {
+    let Some((open, close, alternatives)) = find_brace_expression(units) else {
+        return vec![units.to_vec()];
+    };
+    let prefix = &units[..open];
+    let suffixes = expand_units(&units[close + 1..]);
+    let mut results = Vec::new();
+    for alternative in alternatives {
+        for expanded in expand_units(&alternative) {
+            for suffix in &suffixes {
+                let mut result = prefix.to_vec();
+                result.extend(expanded.iter().cloned());
+                result.extend(suffix.iter().cloned());
+                results.push(result);
+            }
+        }
+    }
+    results
+}
+
+//The first `{...}` that is a real brace expression, as (open index, close index, alternatives).
+//Things like `{}` or `{x}` are not, and stay as they are.
+fn find_brace_expression(units: &[Unit]) -> Option<(usize, usize, Vec<Vec<Unit>>)> {
+    for open in 0..units.len() {
+        if units[open] != Unit::Char('{') {
+            continue;
+        }
+        let mut depth = 0;
+        let mut commas = Vec::new();
+        let mut close = None;
+        for (index, unit) in units.iter().enumerate().skip(open + 1) {
+            match unit {
+                Unit::Char('{') => depth += 1,
+                Unit::Char('}') if depth == 0 => {
+                    close = Some(index);
+                    break;
+                }
+                Unit::Char('}') => depth -= 1,
+                Unit::Char(',') if depth == 0 => commas.push(index),
+                _ => {}
+            }
+        }
+        let Some(close) = close else {
+            continue;
+        };
+        if !commas.is_empty() {
+            let mut alternatives = Vec::new();
+            let mut start = open + 1;
+            for comma in commas.into_iter().chain([close]) {
+                alternatives.push(units[start..comma].to_vec());
+                start = comma + 1;
+            }
+            return Some((open, close, alternatives));
+        }
+        if let Some(sequence) = expand_sequence(&units[open + 1..close]) {
+            let alternatives = sequence
+                .into_iter()
+                .map(|item| item.chars().map(Unit::Char).collect())
+                .collect();
+            return Some((open, close, alternatives));
+        }
+    }
+    None
+}
+
+//`{1..5}`, `{01..10}`, `{10..1..3}`, `{a..e}` and friends.
+fn expand_sequence(units: &[Unit]) -> Option<Vec<String>> {
+    let mut text = String::new();
+    for unit in units {
+        match unit {
+            Unit::Char(c) => text.push(*c),
+            Unit::Part(_) => return None,
+        }
+    }
+    let pieces: Vec<&str> = text.split("..").collect();
+    let (start, end, step) = match pieces.as_slice() {
+        [start, end] => (*start, *end, None),
+        [start, end, step] => (*start, *end, Some(step.parse::<i64>().ok()?)),
+        _ => return None,
+    };
+    let step = step
+        .map(i64::unsigned_abs)
+        .filter(|step| *step != 0)
+        .unwrap_or(1);
+
+    if let (Ok(first), Ok(last)) = (start.parse::<i64>(), end.parse::<i64>()) {
+        let padded = |s: &str| {
+            s.trim_start_matches('-').len() > 1 && s.trim_start_matches('-').starts_with('0')
+        };
+        let width = if padded(start) || padded(end) {
+            start.len().max(end.len())
+        } else {
+            0
+        };
+        let items = stepped_range(first, last, step)?
+            .into_iter()
+            .map(|n| {
+                if n < 0 {
+                    format!(
+                        "-{:0>width$}",
+                        n.unsigned_abs(),
+                        width = width.saturating_sub(1)
+                    )
+                } else {
+                    format!("{:0>width$}", n, width = width)
+                }
+            })
+            .collect();
+        return Some(items);
+    }
+
+    let mut start_chars = start.chars();
+    let mut end_chars = end.chars();
+    match (
+        start_chars.next(),
+        start_chars.next(),
+        end_chars.next(),
+        end_chars.next(),
+    ) {
+        (Some(first), None, Some(last), None)
+            if first.is_ascii_alphabetic() && last.is_ascii_alphabetic() =>
+        {
+            Some(
+                stepped_range(first as i64, last as i64, step)?
+                    .into_iter()
+                    .map(|c| char::from(c as u8).to_string())
+                    .collect(),
+            )
+        }
+        _ => None,
+    }
+}
+
+//None when the range does not fit in i64 arithmetic, which leaves the word unexpanded.
+fn stepped_range(first: i64, last: i64, step: u64) -> Option<Vec<i64>> {
+    let count = (first.abs_diff(last) / step).checked_add(1)?;
+    (0..count)
+        .map(|i| {
+            let offset = i.checked_mul(step)?;
+            if first <= last {
+                first.checked_add_unsigned(offset)
+            } else {
+                first.checked_sub_unsigned(offset)
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(text: &str) -> Vec<String> {
+        let word = Word {
+            parts: vec![WordPart::Literal(text.to_string())],
+        };
+        expand_braces(&word)
+            .into_iter()
+            .map(|word| match word.parts.as_slice() {
+                [WordPart::Literal(text)] => text.clone(),
+                parts => panic!("unexpected parts {:?}", parts),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn lists_nest_and_keep_surrounding_text() {
+        assert_eq!(expand("{a,b,c}"), ["a", "b", "c"]);
+        assert_eq!(expand("x{1,2}y"), ["x1y", "x2y"]);
+        assert_eq!(expand("{a,{b,c}}"), ["a", "b", "c"]);
+        assert_eq!(expand("{a}"), ["{a}"]);
+    }
+
+    #[test]
+    fn ranges_count_up_or_down() {
+        assert_eq!(expand("{1..5}"), ["1", "2", "3", "4", "5"]);
+        assert_eq!(expand("{5..1}"), ["5", "4", "3", "2", "1"]);
+        assert_eq!(expand("{-2..2}"), ["-2", "-1", "0", "1", "2"]);
+        assert_eq!(expand("{e..a}"), ["e", "d", "c", "b", "a"]);
+    }
+
+    #[test]
+    fn steps_ignore_their_sign_and_zero_means_one() {
+        assert_eq!(expand("{1..10..3}"), ["1", "4", "7", "10"]);
+        assert_eq!(expand("{10..1..3}"), ["10", "7", "4", "1"]);
+        assert_eq!(expand("{1..5..-2}"), ["1", "3", "5"]);
+        assert_eq!(expand("{1..3..0}"), ["1", "2", "3"]);
+        assert_eq!(expand("{a..e..2}"), ["a", "c", "e"]);
+    }
+
+    #[test]
+    fn zero_padding_takes_the_widest_end() {
+        assert_eq!(expand("{01..10..4}"), ["01", "05", "09"]);
+        assert_eq!(expand("{-05..5..5}"), ["-05", "000", "005"]);
+    }
+
+    #[test]
+    fn malformed_ranges_stay_literal() {
+        assert_eq!(expand("{1..}"), ["{1..}"]);
+        assert_eq!(expand("{a..5}"), ["{a..5}"]);
+    }
+
+    #[test]
+    fn range_overflowing_i64_is_left_unexpanded() {
+        let word = "{-9223372036854775808..9223372036854775807}";
+        assert_eq!(expand(word), [word]);
+        let word = "{9223372036854775807..-9223372036854775808}";
+        assert_eq!(expand(word), [word]);
+    }
+
+    #[test]
+    fn range_ending_at_i64_limits() {
+        assert_eq!(
+            expand("{9223372036854775806..9223372036854775807}"),
+            ["9223372036854775806", "9223372036854775807"]
+        );
+        assert_eq!(
+            expand("{-9223372036854775807..-9223372036854775808}"),
+            ["-9223372036854775807", "-9223372036854775808"]
+        );
+    }
+}
@@ -0,0 +1,43 @@
+use std::io::{self, Read};
+use std::os::fd::AsRawFd;
+
+use crate::executor::execute_program;
+use crate::executor::state::ShellState;
+use crate::executor::subshell::{fork_shell, wait_for};
+use crate::expansion::{ExpansionError, Fragment};
+use crate::parser::command_parser::parse_input;
+
+pub fn expand_command_substitution(
+    state: &mut ShellState,
+    source: &str,
+    quoted: bool,
+) -> Result<Vec<Fragment>, ExpansionError> {
+    let program = parse_input(source)?;
+    let (mut reader, writer) = io::pipe()?;
+    let (reader_fd, writer_fd) = (reader.as_raw_fd(), writer.as_raw_fd());
+    let pid = fork_shell(|| {
+        unsafe {
+            libc::close(reader_fd);
+            libc::dup2(writer_fd, libc::STDOUT_FILENO);
+            libc::close(writer_fd);
+        }
+        execute_program(state, &program)
+    })?;
+    drop(writer);
+
+    let mut output = Vec::new();
+    reader.read_to_end(&mut output)?;
+    let status = wait_for(pid);
+    state.last_status = status;
+    state.substitution_status = Some(status);
+
+    let mut text = String::from_utf8_lossy(&output).into_owned();
+    let trimmed_len = text.trim_end_matches('\n').len();
+    text.truncate(trimmed_len);
+    Ok(vec![Fragment {
+        text,
+        quoted,
+        splittable: !quoted,
+        starts_field: false,
+    }])
+}
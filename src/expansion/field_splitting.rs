@@ -0,0 +1,122 @@
+use crate::expansion::{Field, Fragment};
+
+//What `$IFS` means when it is unset.
+pub const DEFAULT_IFS: &str = " \t\n";
+
+//Splits the unquoted results of expansions into fields on the characters of `ifs`, the POSIX way:
+//runs of IFS whitespace separate fields and vanish at the edges, while every other IFS character
+//ends exactly one field, even an empty one, together with the whitespace around it.
+pub fn split_fields(fragments: &[Fragment], ifs: &str) -> Vec<Field> {
+    let mut fields = Vec::new();
+    let mut current = Field::default();
+    //Whether `current` is a field yet: it has text, or some quoting that makes even "" count.
+    let mut has_field = false;
+    //Set once whitespace has ended a field, so a `,` right after it does not end another one.
+    let mut after_whitespace = false;
+    for fragment in fragments {
+        if fragment.starts_field && has_field {
+            fields.push(std::mem::take(&mut current));
+            has_field = false;
+        }
+        if !fragment.splittable {
+            fragment
+                .text
+                .chars()
+                .for_each(|c| current.push(c, fragment.quoted));
+            if fragment.quoted || !fragment.text.is_empty() {
+                has_field = true;
+                after_whitespace = false;
+            }
+            continue;
+        }
+        for c in fragment.text.chars() {
+            if !ifs.contains(c) {
+                current.push(c, false);
+                has_field = true;
+                after_whitespace = false;
+            } else if c.is_ascii_whitespace() {
+                if has_field {
+                    fields.push(std::mem::take(&mut current));
+                    has_field = false;
+                    after_whitespace = true;
+                }
+            } else {
+                if has_field || !after_whitespace {
+                    fields.push(std::mem::take(&mut current));
+                }
+                has_field = false;
+                after_whitespace = false;
+            }
+        }
+    }
+    if has_field {
+        fields.push(current);
+    }
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unquoted(text: &str) -> Fragment {
+        Fragment {
+            text: text.to_string(),
+            quoted: false,
+            splittable: true,
+            starts_field: false,
+        }
+    }
+
+    fn quoted(text: &str) -> Fragment {
+        Fragment {
+            text: text.to_string(),
+            quoted: true,
+            splittable: false,
+            starts_field: false,
+        }
+    }
+
+    fn split(fragments: &[Fragment], ifs: &str) -> Vec<String> {
+        split_fields(fragments, ifs)
+            .into_iter()
+            .map(|field| field.text)
+            .collect()
+    }
+
+    #[test]
+    fn whitespace_runs_separate_and_vanish_at_the_edges() {
+        assert_eq!(split(&[unquoted(" a  b ")], DEFAULT_IFS), ["a", "b"]);
+        assert_eq!(split(&[unquoted("a\t\nb")], DEFAULT_IFS), ["a", "b"]);
+        assert!(split(&[unquoted("   ")], DEFAULT_IFS).is_empty());
+    }
+
+    #[test]
+    fn each_non_whitespace_separator_ends_one_field() {
+        assert_eq!(split(&[unquoted("a,,b")], ","), ["a", "", "b"]);
+        assert_eq!(split(&[unquoted(",a")], ","), ["", "a"]);
+        assert_eq!(split(&[unquoted("a,b,")], ","), ["a", "b"]);
+    }
+
+    #[test]
+    fn whitespace_around_a_separator_belongs_to_it() {
+        assert_eq!(
+            split(&[unquoted(" a , b ,, c ")], " ,"),
+            ["a", "b", "", "c"]
+        );
+    }
+
+    #[test]
+    fn empty_ifs_never_splits() {
+        assert_eq!(split(&[unquoted(" a b ")], ""), [" a b "]);
+    }
+
+    #[test]
+    fn quoted_text_joins_the_neighbouring_field() {
+        assert_eq!(
+            split(&[quoted("x"), unquoted("a b"), quoted("y")], DEFAULT_IFS),
+            ["xa", "by"]
+        );
+        assert_eq!(split(&[unquoted(" "), quoted("")], DEFAULT_IFS), [""]);
+    }
+}
@@ -0,0 +1,210 @@
+pub mod arithmetic;
+pub mod brace;
+pub mod command_substitution;
+pub mod field_splitting;
+pub mod parameter;
+pub mod pathname;
+pub mod pattern;
+pub mod tilde;
+
+use thiserror::Error;
+
+use crate::executor::state::ShellState;
+use crate::expansion::arithmetic::{ArithmeticError, evaluate_arithmetic};
+use crate::expansion::brace::expand_braces;
+use crate::expansion::command_substitution::expand_command_substitution;
+use crate::expansion::field_splitting::{DEFAULT_IFS, split_fields};
+use crate::expansion::parameter::expand_parameter;
+use crate::expansion::pathname::expand_pathname;
+use crate::expansion::tilde::expand_tilde;
+use crate::parser::ast::{Word, WordPart};
+use crate::parser::command_parser::SyntaxError;
+
+#[derive(Debug, Error)]
+pub enum ExpansionError {
+    #[error("{0}: {1}")]
+    ParameterUnset(String, String),
+    #[error("${0}: cannot assign in this way")]
+    CannotAssign(String),
+    #[error("{0}")]
+    Syntax(#[from] SyntaxError),
+    #[error("{0}: {1}")]
+    Arithmetic(String, ArithmeticError),
+    #[error("command substitution: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("cannot create temp file for here-document: {0}")]
+    HereDocument(std::io::Error),
+}
+
+//A piece of an expanded word. Only unquoted text produced by an expansion is split into fields.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fragment {
+    pub text: String,
+    pub quoted: bool,
+    pub splittable: bool,
+    //Set on the second and later arguments of `$@`, which never share a field.
+    pub starts_field: bool,
+}
+
+//A word after field splitting. `pattern` is the same text with quoted glob characters escaped.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Field {
+    pub text: String,
+    pub pattern: String,
+    pub has_glob: bool,
+}
+
+impl Field {
+    fn push(&mut self, c: char, quoted: bool) {
+        self.text.push(c);
+        if quoted && matches!(c, '*' | '?' | '[' | ']' | '\\') {
+            self.pattern.push('\\');
+        }
+        if !quoted && matches!(c, '*' | '?' | '[') {
+            self.has_glob = true;
+        }
+        self.pattern.push(c);
+    }
+}
+
+//Expands a word into the fields a command sees as arguments.
+pub fn expand_word(state: &mut ShellState, word: &Word) -> Result<Vec<String>, ExpansionError> {
+    let word = expand_tilde(state, word, false);
+    let mut fragments = Vec::new();
+    expand_parts(state, &word.parts, false, &mut fragments)?;
+    let ifs = state.get_var("IFS").unwrap_or(DEFAULT_IFS);
+    let mut expanded = Vec::new();
+    for field in split_fields(&fragments, ifs) {
+        let matches = if field.has_glob {
+            expand_pathname(&field.pattern)
+        } else {
+            Vec::new()
+        };
+        //A pattern that matches nothing is left as written.
+        if matches.is_empty() {
+            expanded.push(field.text);
+        } else {
+            expanded.extend(matches);
+        }
+    }
+    Ok(expanded)
+}
+
+pub fn expand_words(state: &mut ShellState, words: &[Word]) -> Result<Vec<String>, ExpansionError> {
+    let mut fields = Vec::new();
+    for word in words.iter().flat_map(expand_braces) {
+        fields.extend(expand_word(state, &word)?);
+    }
+    Ok(fields)
+}
+
+//Expands a word where only a single string makes sense, like a redirect target.
+pub fn expand_word_to_string(
+    state: &mut ShellState,
+    word: &Word,
+) -> Result<String, ExpansionError> {
+    let word = expand_tilde(state, word, false);
+    expand_parts_to_string(state, &word.parts)
+}
+
+//The value of `NAME=value`, where a tilde after any `:` is expanded as well.
+pub fn expand_assignment_value(
+    state: &mut ShellState,
+    word: &Word,
+) -> Result<String, ExpansionError> {
+    let word = expand_tilde(state, word, true);
+    expand_parts_to_string(state, &word.parts)
+}
+
+fn expand_parts_to_string(
+    state: &mut ShellState,
+    parts: &[WordPart],
+) -> Result<String, ExpansionError> {
+    let mut fragments = Vec::new();
+    expand_parts(state, parts, false, &mut fragments)?;
+    Ok(fragments
+        .iter()
+        .map(|fragment| fragment.text.as_str())
+        .collect())
+}
+
+//Expands the word holding an arithmetic expression, then evaluates it.
+pub fn expand_arithmetic(state: &mut ShellState, expression: &Word) -> Result<i64, ExpansionError> {
+    let expression = expand_parts_to_string(state, &expression.parts)?;
+    evaluate_arithmetic(state, &expression)
+        .map_err(|err| ExpansionError::Arithmetic(expression.clone(), err))
+}
+
+//Expands a word used as a pattern, like in `case`, where quoted characters match only themselves.
+pub fn expand_pattern(state: &mut ShellState, word: &Word) -> Result<String, ExpansionError> {
+    let word = expand_tilde(state, word, false);
+    let mut fragments = Vec::new();
+    expand_parts(state, &word.parts, false, &mut fragments)?;
+    let mut field = Field::default();
+    for fragment in &fragments {
+        fragment
+            .text
+            .chars()
+            .for_each(|c| field.push(c, fragment.quoted));
+    }
+    Ok(field.pattern)
+}
+
+pub fn expand_parts(
+    state: &mut ShellState,
+    parts: &[WordPart],
+    quoted: bool,
+    fragments: &mut Vec<Fragment>,
+) -> Result<(), ExpansionError> {
+    for part in parts {
+        match part {
+            WordPart::Literal(text) => fragments.push(Fragment {
+                text: text.clone(),
+                quoted,
+                splittable: false,
+                starts_field: false,
+            }),
+            WordPart::Quoted(text) => fragments.push(Fragment {
+                text: text.clone(),
+                quoted: true,
+                splittable: false,
+                starts_field: false,
+            }),
+            WordPart::DoubleQuoted(inner) => {
+                //Even `""` has to leave an (empty) argument behind, but `"$@"` without
+                //arguments leaves nothing.
+                if !(is_all_arguments(inner) && state.positional.is_empty()) {
+                    fragments.push(Fragment {
+                        text: String::new(),
+                        quoted: true,
+                        splittable: false,
+                        starts_field: false,
+                    });
+                }
+                expand_parts(state, inner, true, fragments)?;
+            }
+            WordPart::Parameter(parameter) => {
+                fragments.extend(expand_parameter(state, parameter, quoted)?);
+            }
+            WordPart::CommandSubstitution(source) => {
+                fragments.extend(expand_command_substitution(state, source, quoted)?);
+            }
+            WordPart::Arithmetic(expression) => {
+                let value = expand_arithmetic(state, expression)?;
+                fragments.push(Fragment {
+                    text: value.to_string(),
+                    quoted,
+                    splittable: !quoted,
+                    starts_field: false,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+//True for a bare `$@` or `${@}`.
+fn is_all_arguments(parts: &[WordPart]) -> bool {
+    matches!(parts, [WordPart::Parameter(parameter)]
+        if parameter.name == "@" && parameter.modifier.is_none())
+}
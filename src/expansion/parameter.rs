@@ -0,0 +1,84 @@
+use crate::executor::state::ShellState;
+use crate::expansion::tilde::expand_tilde;
+use crate::expansion::{
+    ExpansionError, Fragment, expand_assignment_value, expand_parts, expand_word_to_string,
+};
+use crate::parser::ast::{ParameterExpansion, ParameterOp, is_valid_name};
+
+pub fn expand_parameter(
+    state: &mut ShellState,
+    parameter: &ParameterExpansion,
+    quoted: bool,
+) -> Result<Vec<Fragment>, ExpansionError> {
+    let value = state.lookup_parameter(&parameter.name);
+    let Some(modifier) = &parameter.modifier else {
+        return Ok(match parameter.name.as_str() {
+            "@" | "*" if !quoted || parameter.name == "@" => argument_fragments(state, quoted),
+            _ => value_fragment(value.unwrap_or_default(), quoted),
+        });
+    };
+    //`${x-word}` only cares whether x is set, `${x:-word}` also treats empty as unset.
+    let is_set = match &value {
+        Some(value) => !(modifier.check_null && value.is_empty()),
+        None => false,
+    };
+    match (modifier.op, is_set) {
+        (ParameterOp::UseDefault, false) | (ParameterOp::UseAlternate, true) => {
+            let word = expand_tilde(state, &modifier.word, false);
+            let mut fragments = Vec::new();
+            expand_parts(state, &word.parts, quoted, &mut fragments)?;
+            for fragment in &mut fragments {
+                fragment.splittable = !fragment.quoted;
+            }
+            Ok(fragments)
+        }
+        (ParameterOp::UseAlternate, false) => Ok(Vec::new()),
+        (ParameterOp::AssignDefault, false) => {
+            if !is_valid_name(&parameter.name) {
+                return Err(ExpansionError::CannotAssign(parameter.name.clone()));
+            }
+            let assigned = expand_assignment_value(state, &modifier.word)?;
+            state.set_var(&parameter.name, assigned.clone());
+            Ok(value_fragment(assigned, quoted))
+        }
+        (ParameterOp::ErrorIfUnset, false) => {
+            let mut message = expand_word_to_string(state, &modifier.word)?;
+            if message.is_empty() {
+                message = if modifier.check_null {
+                    "parameter null or not set".to_string()
+                } else {
+                    "parameter not set".to_string()
+                };
+            }
+            Err(ExpansionError::ParameterUnset(
+                parameter.name.clone(),
+                message,
+            ))
+        }
+        (_, true) => Ok(value_fragment(value.unwrap_or_default(), quoted)),
+    }
+}
+
+fn value_fragment(text: String, quoted: bool) -> Vec<Fragment> {
+    vec![Fragment {
+        text,
+        quoted,
+        splittable: !quoted,
+        starts_field: false,
+    }]
+}
+
+//`$@` and unquoted `$*`: one field per positional parameter, each split again when unquoted.
+fn argument_fragments(state: &ShellState, quoted: bool) -> Vec<Fragment> {
+    state
+        .positional
+        .iter()
+        .enumerate()
+        .map(|(index, argument)| Fragment {
+            text: argument.clone(),
+            quoted,
+            splittable: !quoted,
+            starts_field: index > 0,
+        })
+        .collect()
+}
@@ -0,0 +1,58 @@
+use std::fs;
+use std::path::Path;
+
+use crate::expansion::pattern::{has_glob_chars, pattern_matches, unescape_pattern};
+
+//Expands a glob pattern against the filesystem. Returns the sorted matches, empty when nothing matches.
+pub fn expand_pathname(pattern: &str) -> Vec<String> {
+    let mut paths = vec![String::new()];
+    let components: Vec<&str> = pattern.split('/').collect();
+    for (index, component) in components.iter().enumerate() {
+        let is_last = index == components.len() - 1;
+        if index > 0 {
+            paths.iter_mut().for_each(|path| path.push('/'));
+        }
+        if component.is_empty() {
+            //A leading, doubled or trailing slash. A trailing one only keeps directories.
+            if is_last {
+                paths.retain(|path| Path::new(path).is_dir());
+            }
+            continue;
+        }
+        if !has_glob_chars(component) {
+            let name = unescape_pattern(component);
+            paths.iter_mut().for_each(|path| path.push_str(&name));
+            continue;
+        }
+        let mut matched = Vec::new();
+        for path in &paths {
+            matched.extend(
+                matching_entries(path, component)
+                    .into_iter()
+                    .map(|name| format!("{}{}", path, name)),
+            );
+        }
+        paths = matched;
+        if !is_last {
+            paths.retain(|path| Path::new(path).is_dir());
+        }
+    }
+    paths.retain(|path| !path.is_empty() && fs::symlink_metadata(path).is_ok());
+    paths.sort();
+    paths
+}
+
+//Names in `directory` matching one path component. Dotfiles need the pattern to start with a literal dot.
+fn matching_entries(directory: &str, component: &str) -> Vec<String> {
+    let directory = if directory.is_empty() { "." } else { directory };
+    let Ok(entries) = fs::read_dir(directory) else {
+        return Vec::new();
+    };
+    let allow_hidden = component.starts_with('.') || component.starts_with("\\.");
+    entries
+        .flatten()
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| allow_hidden || !name.starts_with('.'))
+        .filter(|name| pattern_matches(component, name))
+        .collect()
+}
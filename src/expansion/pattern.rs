@@ -0,0 +1,142 @@
+//Shell pattern matching: `*`, `?`, `[...]` bracket expressions and backslash escapes.
+
+pub fn pattern_matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut pi, mut ti) = (0, 0);
+    //Where the last `*` was seen and how much text it has swallowed so far, to retry on a mismatch.
+    let mut star: Option<(usize, usize)> = None;
+    while ti < text.len() {
+        if pattern.get(pi) == Some(&'*') {
+            star = Some((pi, ti));
+            pi += 1;
+            continue;
+        }
+        if pi < pattern.len()
+            && let Some(next) = match_one(&pattern, pi, text[ti])
+        {
+            pi = next;
+            ti += 1;
+            continue;
+        }
+        match star {
+            Some((star_pi, star_ti)) => {
+                pi = star_pi + 1;
+                ti = star_ti + 1;
+                star = Some((star_pi, star_ti + 1));
+            }
+            None => return false,
+        }
+    }
+    pattern[pi..].iter().all(|&c| c == '*')
+}
+
+//True when the pattern has an unescaped `*`, `?` or `[`, i.e. it is more than a literal string.
+pub fn has_glob_chars(pattern: &str) -> bool {
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '*' | '?' | '[' => return true,
+            _ => {}
+        }
+    }
+    false
+}
+
+//Drops the escaping backslashes from a pattern with no glob characters left in it.
+pub fn unescape_pattern(pattern: &str) -> String {
+    let mut result = String::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => result.extend(chars.next()),
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+//Matches a single non-`*` pattern element at `pi` against `c`, returning where the next element starts.
+fn match_one(pattern: &[char], pi: usize, c: char) -> Option<usize> {
+    match pattern[pi] {
+        '?' => Some(pi + 1),
+        '[' => match match_bracket(pattern, pi, c) {
+            Some((true, next)) => Some(next),
+            Some((false, _)) => None,
+            //No closing `]`, so the `[` is just a character.
+            None => (c == '[').then_some(pi + 1),
+        },
+        '\\' if pi + 1 < pattern.len() => (pattern[pi + 1] == c).then_some(pi + 2),
+        literal => (literal == c).then_some(pi + 1),
+    }
+}
+
+fn match_bracket(pattern: &[char], start: usize, c: char) -> Option<(bool, usize)> {
+    let mut i = start + 1;
+    let negated = matches!(pattern.get(i), Some('!' | '^'));
+    if negated {
+        i += 1;
+    }
+    let mut matched = false;
+    let mut first = true;
+    loop {
+        let current = *pattern.get(i)?;
+        if current == ']' && !first {
+            return Some((matched != negated, i + 1));
+        }
+        first = false;
+        if current == '['
+            && pattern.get(i + 1) == Some(&':')
+            && let Some(end) = find_class_end(pattern, i + 2)
+        {
+            let class: String = pattern[i + 2..end].iter().collect();
+            matched |= class_matches(&class, c);
+            i = end + 2;
+            continue;
+        }
+        let (low, after_low) = bracket_char(pattern, i)?;
+        if pattern.get(after_low) == Some(&'-')
+            && pattern.get(after_low + 1).is_some_and(|&c| c != ']')
+        {
+            let (high, after_high) = bracket_char(pattern, after_low + 1)?;
+            matched |= low <= c && c <= high;
+            i = after_high;
+        } else {
+            matched |= low == c;
+            i = after_low;
+        }
+    }
+}
+
+fn bracket_char(pattern: &[char], i: usize) -> Option<(char, usize)> {
+    match *pattern.get(i)? {
+        '\\' => Some((*pattern.get(i + 1)?, i + 2)),
+        c => Some((c, i + 1)),
+    }
+}
+
+//Finds the `:` of the closing `:]` of a `[:class:]`.
+fn find_class_end(pattern: &[char], from: usize) -> Option<usize> {
+    (from..pattern.len().saturating_sub(1)).find(|&i| pattern[i] == ':' && pattern[i + 1] == ']')
+}
+
+fn class_matches(class: &str, c: char) -> bool {
+    match class {
+        "alpha" => c.is_alphabetic(),
+        "digit" => c.is_ascii_digit(),
+        "alnum" => c.is_alphanumeric(),
+        "upper" => c.is_uppercase(),
+        "lower" => c.is_lowercase(),
+        "space" => c.is_whitespace(),
+        "blank" => c == ' ' || c == '\t',
+        "punct" => c.is_ascii_punctuation(),
+        "print" => !c.is_control(),
+        "graph" => !c.is_control() && !c.is_whitespace(),
+        "cntrl" => c.is_control(),
+        "xdigit" => c.is_ascii_hexdigit(),
+        _ => false,
+    }
+}
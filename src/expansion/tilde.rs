@@ -0,0 +1,76 @@
+use std::ffi::{CStr, CString};
+
+use crate::executor::state::ShellState;
+use crate::parser::ast::{Word, WordPart};
+
+//Replaces a leading `~`, `~/...`, `~user`, `~+` or `~-` with the directory it names. In an
+//assignment a tilde straight after a `:` counts too, as in `PATH=~/bin:~/.local/bin`.
+pub fn expand_tilde(state: &ShellState, word: &Word, assignment: bool) -> Word {
+    let mut parts = Vec::new();
+    for (index, part) in word.parts.iter().enumerate() {
+        let WordPart::Literal(text) = part else {
+            parts.push(part.clone());
+            continue;
+        };
+        let is_last_part = index == word.parts.len() - 1;
+        let mut literal = String::new();
+        let mut rest = text.as_str();
+        let mut at_start = index == 0;
+        while !rest.is_empty() {
+            if at_start && let Some(after_tilde) = rest.strip_prefix('~') {
+                let prefix_end = after_tilde
+                    .find(|c| c == '/' || (assignment && c == ':'))
+                    .unwrap_or(after_tilde.len());
+                //`~$USER` and `~"name"` are left alone: the whole prefix has to be plain text.
+                let prefix_complete = prefix_end < after_tilde.len() || is_last_part;
+                if prefix_complete
+                    && let Some(directory) = tilde_directory(state, &after_tilde[..prefix_end])
+                {
+                    if !literal.is_empty() {
+                        parts.push(WordPart::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(WordPart::Quoted(directory));
+                    rest = &after_tilde[prefix_end..];
+                    at_start = false;
+                    continue;
+                }
+            }
+            let c = rest.chars().next().expect("rest is not empty");
+            literal.push(c);
+            rest = &rest[c.len_utf8()..];
+            at_start = assignment && c == ':';
+        }
+        if !literal.is_empty() {
+            parts.push(WordPart::Literal(literal));
+        }
+    }
+    Word { parts }
+}
+
+fn tilde_directory(state: &ShellState, prefix: &str) -> Option<String> {
+    match prefix {
+        "" => match state.get_var("HOME") {
+            Some(home) => Some(home.to_string()),
+            None => user_home(None),
+        },
+        "+" => state.get_var("PWD").map(str::to_string),
+        "-" => state.get_var("OLDPWD").map(str::to_string),
+        user => user_home(Some(user)),
+    }
+}
+
+//Looks a user up in the passwd database, or the current user when `user` is `None`.
+fn user_home(user: Option<&str>) -> Option<String> {
+    let entry = match user {
+        Some(user) => {
+            let name = CString::new(user).ok()?;
+            unsafe { libc::getpwnam(name.as_ptr()) }
+        }
+        None => unsafe { libc::getpwuid(libc::getuid()) },
+    };
+    if entry.is_null() {
+        return None;
+    }
+    let directory = unsafe { CStr::from_ptr((*entry).pw_dir) };
+    Some(directory.to_string_lossy().into_owned())
+}
@@ -0,0 +1,410 @@
+use std::fmt;
+use std::rc::Rc;
+
+use crate::utilities::redirect::Redirect;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WordPart {
+    Literal(String),
+    Quoted(String),
+    DoubleQuoted(Vec<WordPart>),
+    Parameter(ParameterExpansion),
+    //The source text between `$(` and `)`, parsed when it is expanded.
+    CommandSubstitution(String),
+    //The body of `$((...))`, which may itself hold `$var` and `$(cmd)` expansions.
+    Arithmetic(Word),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParameterOp {
+    UseDefault,
+    AssignDefault,
+    ErrorIfUnset,
+    UseAlternate,
+}
+
+//The `:-word` style tail of `${name:-word}`. Without the colon only unset counts, not empty.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParameterModifier {
+    pub op: ParameterOp,
+    pub check_null: bool,
+    pub word: Word,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParameterExpansion {
+    pub name: String,
+    pub modifier: Option<ParameterModifier>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Word {
+    pub parts: Vec<WordPart>,
+}
+
+impl Word {
+    //True for a bare unquoted word such as a reserved word like `!`.
+    pub fn is_literal(&self, text: &str) -> bool {
+        matches!(self.parts.as_slice(), [WordPart::Literal(literal)] if literal == text)
+    }
+
+    //Splits `NAME=value` into the name and the still unexpanded value.
+    pub fn as_assignment(&self) -> Option<(String, Word)> {
+        let Some(WordPart::Literal(first)) = self.parts.first() else {
+            return None;
+        };
+        let (name, value) = first.split_once('=')?;
+        if !is_valid_name(name) {
+            return None;
+        }
+        let mut parts = Vec::new();
+        if !value.is_empty() {
+            parts.push(WordPart::Literal(value.to_string()));
+        }
+        parts.extend(self.parts[1..].iter().cloned());
+        Some((name.to_string(), Word { parts }))
+    }
+}
+
+pub fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+//Writes a word back out in a form the lexer would read the same way.
+impl fmt::Display for Word {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for part in &self.parts {
+            write!(f, "{}", part)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for WordPart {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WordPart::Literal(text) => write!(f, "{}", text),
+            WordPart::Quoted(text) => write!(f, "'{}'", text.replace('\'', "'\\''")),
+            WordPart::DoubleQuoted(inner) => {
+                write!(f, "\"")?;
+                for part in inner {
+                    match part {
+                        WordPart::Literal(text) => {
+                            for c in text.chars() {
+                                if matches!(c, '"' | '\\' | '$' | '`') {
+                                    write!(f, "\\")?;
+                                }
+                                write!(f, "{}", c)?;
+                            }
+                        }
+                        _ => write!(f, "{}", part)?,
+                    }
+                }
+                write!(f, "\"")
+            }
+            WordPart::Parameter(parameter) => write!(f, "{}", parameter),
+            WordPart::CommandSubstitution(source) => write!(f, "$({})", source),
+            WordPart::Arithmetic(expression) => write!(f, "$(({}))", expression),
+        }
+    }
+}
+
+impl fmt::Display for ParameterExpansion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(modifier) = &self.modifier else {
+            return write!(f, "${{{}}}", self.name);
+        };
+        let colon = if modifier.check_null { ":" } else { "" };
+        let op = match modifier.op {
+            ParameterOp::UseDefault => '-',
+            ParameterOp::AssignDefault => '=',
+            ParameterOp::ErrorIfUnset => '?',
+            ParameterOp::UseAlternate => '+',
+        };
+        write!(f, "${{{}{}{}{}}}", self.name, colon, op, modifier.word)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Redirection {
+    pub kind: Redirect,
+    pub target: Word,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Assignment {
+    pub name: String,
+    pub value: Word,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SimpleCommand {
+    pub assignments: Vec<Assignment>,
+    pub words: Vec<Word>,
+    pub redirections: Vec<Redirection>,
+}
+
+//Commands that hold other commands, like `( list )` and `{ list; }`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompoundCommand {
+    Subshell(Program),
+    BraceGroup(Program),
+    If(IfCommand),
+    While(WhileCommand),
+    For(ForCommand),
+    ArithmeticFor(ArithmeticForCommand),
+    Case(CaseCommand),
+}
+
+//`while condition; do body; done`, or `until` when the condition has to fail.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WhileCommand {
+    pub until: bool,
+    pub condition: Program,
+    pub body: Program,
+}
+
+//`for name in words; do body; done`. Without `in` the words are the positional parameters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForCommand {
+    pub name: String,
+    pub words: Option<Vec<Word>>,
+    pub body: Program,
+}
+
+//`for ((init; condition; step)); do body; done`. An empty condition counts as true.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArithmeticForCommand {
+    pub init: Word,
+    pub condition: Word,
+    pub step: Word,
+    pub body: Program,
+}
+
+//`case word in pattern | pattern) body ;; ... esac`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaseCommand {
+    pub word: Word,
+    pub items: Vec<CaseItem>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaseItem {
+    pub patterns: Vec<Word>,
+    pub body: Program,
+    pub terminator: CaseTerminator,
+}
+
+//What happens after a matching item's body has run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaseTerminator {
+    //`;;` ends the case.
+    Break,
+    //`;&` runs the next body too, without testing its patterns.
+    FallThrough,
+    //`;;&` goes on testing the following patterns.
+    Continue,
+}
+
+//`if c1; then b1; elif c2; then b2; else b3; fi`, as (condition, body) pairs tried in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IfCommand {
+    pub branches: Vec<(Program, Program)>,
+    pub else_body: Option<Program>,
+}
+
+//`name() compound-command`. The body is shared with the function table once defined.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionDefinition {
+    pub name: String,
+    pub body: Rc<Command>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Simple(SimpleCommand),
+    Compound(CompoundCommand, Vec<Redirection>),
+    Function(FunctionDefinition),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Pipeline {
+    pub negated: bool,
+    pub commands: Vec<Command>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListOperator {
+    And,
+    Or,
+}
+
+//`first && second || third`, run left to right on exit statuses.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AndOrList {
+    pub first: Pipeline,
+    pub rest: Vec<(ListOperator, Pipeline)>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Program {
+    pub lists: Vec<AndOrList>,
+}
+
+//Indents every line of a nested body, for printing commands back out.
+fn indented(program: &Program) -> String {
+    program
+        .to_string()
+        .lines()
+        .map(|line| format!("    {}\n", line))
+        .collect()
+}
+
+//Writes commands back out as shell source, one command per line, for `type`.
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, list) in self.lists.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", list)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for AndOrList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.first)?;
+        for (operator, pipeline) in &self.rest {
+            let operator = match operator {
+                ListOperator::And => "&&",
+                ListOperator::Or => "||",
+            };
+            write!(f, " {} {}", operator, pipeline)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Pipeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.negated {
+            write!(f, "! ")?;
+        }
+        for (index, command) in self.commands.iter().enumerate() {
+            if index > 0 {
+                write!(f, " | ")?;
+            }
+            write!(f, "{}", command)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Command::Simple(command) => write!(f, "{}", command),
+            Command::Compound(command, redirections) => {
+                write!(f, "{}", command)?;
+                for redirection in redirections {
+                    write!(f, " {}", redirection)?;
+                }
+                Ok(())
+            }
+            Command::Function(definition) => write!(f, "{}", definition),
+        }
+    }
+}
+
+impl fmt::Display for FunctionDefinition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ()\n{}", self.name, self.body)
+    }
+}
+
+impl fmt::Display for SimpleCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let assignments = self
+            .assignments
+            .iter()
+            .map(|assignment| format!("{}={}", assignment.name, assignment.value));
+        let words = self.words.iter().map(Word::to_string);
+        let redirections = self.redirections.iter().map(Redirection::to_string);
+        let parts: Vec<String> = assignments.chain(words).chain(redirections).collect();
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+impl fmt::Display for Redirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.kind, self.target)
+    }
+}
+
+impl fmt::Display for CompoundCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompoundCommand::Subshell(body) => write!(f, "(\n{})", indented(body)),
+            CompoundCommand::BraceGroup(body) => write!(f, "{{\n{}}}", indented(body)),
+            CompoundCommand::If(command) => {
+                for (index, (condition, body)) in command.branches.iter().enumerate() {
+                    let keyword = if index == 0 { "if" } else { "elif" };
+                    write!(f, "{} {}; then\n{}", keyword, condition, indented(body))?;
+                }
+                if let Some(body) = &command.else_body {
+                    write!(f, "else\n{}", indented(body))?;
+                }
+                write!(f, "fi")
+            }
+            CompoundCommand::While(command) => {
+                let keyword = if command.until { "until" } else { "while" };
+                write!(
+                    f,
+                    "{} {}; do\n{}done",
+                    keyword,
+                    command.condition,
+                    indented(&command.body)
+                )
+            }
+            CompoundCommand::For(command) => {
+                write!(f, "for {}", command.name)?;
+                if let Some(words) = &command.words {
+                    write!(f, " in")?;
+                    for word in words {
+                        write!(f, " {}", word)?;
+                    }
+                }
+                write!(f, "; do\n{}done", indented(&command.body))
+            }
+            CompoundCommand::ArithmeticFor(command) => write!(
+                f,
+                "for (({}; {}; {})); do\n{}done",
+                command.init.to_string().trim(),
+                command.condition.to_string().trim(),
+                command.step.to_string().trim(),
+                indented(&command.body)
+            ),
+            CompoundCommand::Case(command) => {
+                writeln!(f, "case {} in", command.word)?;
+                for item in &command.items {
+                    let patterns: Vec<String> = item.patterns.iter().map(Word::to_string).collect();
+                    writeln!(f, "    {})", patterns.join(" | "))?;
+                    for line in indented(&item.body).lines() {
+                        writeln!(f, "    {}", line)?;
+                    }
+                    let terminator = match item.terminator {
+                        CaseTerminator::Break => ";;",
+                        CaseTerminator::FallThrough => ";&",
+                        CaseTerminator::Continue => ";;&",
+                    };
+                    writeln!(f, "    {}", terminator)?;
+                }
+                write!(f, "esac")
+            }
+        }
+    }
+}
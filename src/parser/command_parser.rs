@@ -0,0 +1,589 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use thiserror::Error;
+
+use crate::parser::ast::{
+    AndOrList, ArithmeticForCommand, Assignment, CaseCommand, CaseItem, CaseTerminator, Command,
+    CompoundCommand, ForCommand, FunctionDefinition, IfCommand, ListOperator, Pipeline, Program,
+    Redirection, SimpleCommand, WhileCommand, is_valid_name,
+};
+use crate::parser::lexer::{Lexer, Operator, Token, arithmetic_word};
+use crate::utilities::redirect::Redirect;
+
+#[derive(Debug, Error)]
+pub enum ParseError {
+    #[error("syntax error near unexpected token '{0}'")]
+    UnexpectedToken(String),
+    #[error("{0}: bad substitution")]
+    BadSubstitution(String),
+    #[error("unexpected EOF while looking for matching `{0}'")]
+    UnterminatedQuote(char),
+    #[error("syntax error: unexpected end of file")]
+    UnexpectedEnd,
+    #[error("here-document delimited by end-of-file (wanted `{0}')")]
+    UnterminatedHereDoc(String),
+}
+
+impl ParseError {
+    //Input that could still become valid with more lines, so the editor should keep reading.
+    pub fn is_incomplete(&self) -> bool {
+        matches!(
+            self,
+            ParseError::UnterminatedQuote(_)
+                | ParseError::UnexpectedEnd
+                | ParseError::UnterminatedHereDoc(_)
+        )
+    }
+}
+
+//Words that are keywords when they appear unquoted where a command can start.
+pub const RESERVED_WORDS: &[&str] = &[
+    "!", "{", "}", "if", "then", "elif", "else", "fi", "while", "until", "for", "in", "do", "done",
+    "case", "esac", "function",
+];
+
+//Reserved words that end a list of commands, like the `then` after an `if` condition.
+const LIST_TERMINATORS: &[&str] = &["}", "then", "elif", "else", "fi", "do", "done", "esac"];
+
+//A parse error and the byte offset in the input where it was found.
+#[derive(Debug, Error)]
+#[error("{error}")]
+pub struct SyntaxError {
+    pub error: ParseError,
+    pub offset: usize,
+}
+
+impl SyntaxError {
+    //1-based line and column (in characters) of the error.
+    pub fn line_column(&self, input: &str) -> (usize, usize) {
+        let before = &input[..self.offset.min(input.len())];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+        (line, before[line_start..].chars().count() + 1)
+    }
+
+    //The message plus the offending line with a caret under the bad spot.
+    pub fn report(&self, input: &str) -> String {
+        let (line, column) = self.line_column(input);
+        let text = input.lines().nth(line - 1).unwrap_or("");
+        //Tabs are copied so the caret lines up however wide the terminal draws them.
+        let padding: String = text
+            .chars()
+            .take(column - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        format!(
+            "myshell: {}\n  line {}, column {}:\n  {}\n  {}^",
+            self.error, line, column, text, padding
+        )
+    }
+}
+
+pub fn parse_input(input: &str) -> Result<Program, SyntaxError> {
+    parse_with_aliases(input, &HashMap::new())
+}
+
+//Like `parse_input`, but a word in command position that names an alias is replaced by its value.
+pub fn parse_with_aliases(
+    input: &str,
+    aliases: &HashMap<String, String>,
+) -> Result<Program, SyntaxError> {
+    let (tokens, offsets): (Vec<Token>, Vec<usize>) = Lexer::new(input)
+        .tokenize()?
+        .into_iter()
+        .filter(|(token, _)| !matches!(token, Token::Comment(_)))
+        .unzip();
+    let mut parser = Parser {
+        blocked: vec![Vec::new(); tokens.len()],
+        tokens,
+        offsets,
+        end: input.len(),
+        position: 0,
+        aliases,
+    };
+    parser.parse_program().map_err(|error| SyntaxError {
+        error,
+        offset: parser.offset(),
+    })
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    offsets: Vec<usize>,
+    end: usize,
+    position: usize,
+    aliases: &'a HashMap<String, String>,
+    //For each token, the aliases it came out of. Those are not expanded again, which stops
+    //`alias ls='ls -F'` from looping.
+    blocked: Vec<Vec<String>>,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn advance(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    //Where the current token starts, or the end of the input once past the last one.
+    fn offset(&self) -> usize {
+        self.offsets.get(self.position).copied().unwrap_or(self.end)
+    }
+
+    fn unexpected(&self) -> ParseError {
+        match self.peek() {
+            Some(token) => ParseError::UnexpectedToken(token.to_string()),
+            None => ParseError::UnexpectedToken(Token::Newline.to_string()),
+        }
+    }
+
+    //Like `unexpected`, but running out of input means the command is not finished yet.
+    fn expected_more(&self) -> ParseError {
+        match self.peek() {
+            Some(_) => self.unexpected(),
+            None => ParseError::UnexpectedEnd,
+        }
+    }
+
+    //Reserved words like `{` and `}` only count as unquoted words in command position.
+    fn at_reserved_word(&self, reserved: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word(word)) if word.is_literal(reserved))
+    }
+
+    fn expect_reserved_word(&mut self, reserved: &str) -> Result<(), ParseError> {
+        if !self.at_reserved_word(reserved) {
+            return Err(self.expected_more());
+        }
+        self.advance();
+        Ok(())
+    }
+
+    fn skip_newlines(&mut self) {
+        while let Some(Token::Newline) = self.peek() {
+            self.advance();
+        }
+    }
+
+    //After `|`, `&&` or `||` the command goes on, possibly on the next line.
+    fn skip_newlines_after_operator(&mut self) -> Result<(), ParseError> {
+        self.skip_newlines();
+        if self.peek().is_none() {
+            return Err(ParseError::UnexpectedEnd);
+        }
+        Ok(())
+    }
+
+    fn parse_program(&mut self) -> Result<Program, ParseError> {
+        let program = self.parse_compound_list()?;
+        match self.peek() {
+            None => Ok(program),
+            Some(_) => Err(self.unexpected()),
+        }
+    }
+
+    //And-or lists separated by `;` or newlines, up to whatever closes the enclosing command.
+    fn parse_compound_list(&mut self) -> Result<Program, ParseError> {
+        let mut program = Program::default();
+        loop {
+            self.skip_newlines();
+            if self.at_list_end() {
+                return Ok(program);
+            }
+            program.lists.push(self.parse_and_or()?);
+            match self.peek() {
+                Some(Token::Operator(Operator::Semicolon) | Token::Newline) => {
+                    self.advance();
+                }
+                _ => return Ok(program),
+            }
+        }
+    }
+
+    fn at_list_end(&self) -> bool {
+        match self.peek() {
+            None
+            | Some(Token::Operator(
+                Operator::RightParen
+                | Operator::DoubleSemicolon
+                | Operator::SemicolonAmpersand
+                | Operator::DoubleSemicolonAmpersand,
+            )) => true,
+            _ => LIST_TERMINATORS
+                .iter()
+                .any(|reserved| self.at_reserved_word(reserved)),
+        }
+    }
+
+    //The body of a compound command, which must hold at least one command.
+    fn parse_compound_body(&mut self) -> Result<Program, ParseError> {
+        let body = self.parse_compound_list()?;
+        if body.lists.is_empty() {
+            return Err(self.expected_more());
+        }
+        Ok(body)
+    }
+
+    fn parse_and_or(&mut self) -> Result<AndOrList, ParseError> {
+        let mut list = AndOrList {
+            first: self.parse_pipeline()?,
+            rest: Vec::new(),
+        };
+        loop {
+            let operator = match self.peek() {
+                Some(Token::Operator(Operator::AndIf)) => ListOperator::And,
+                Some(Token::Operator(Operator::OrIf)) => ListOperator::Or,
+                _ => return Ok(list),
+            };
+            self.advance();
+            self.skip_newlines_after_operator()?;
+            list.rest.push((operator, self.parse_pipeline()?));
+        }
+    }
+
+    fn parse_pipeline(&mut self) -> Result<Pipeline, ParseError> {
+        let mut pipeline = Pipeline::default();
+        if let Some(Token::Word(word)) = self.peek()
+            && word.is_literal("!")
+        {
+            self.advance();
+            pipeline.negated = true;
+        }
+        pipeline.commands.push(self.parse_command()?);
+        while let Some(Token::Operator(Operator::Pipe)) = self.peek() {
+            self.advance();
+            self.skip_newlines_after_operator()?;
+            pipeline.commands.push(self.parse_command()?);
+        }
+        Ok(pipeline)
+    }
+
+    fn parse_command(&mut self) -> Result<Command, ParseError> {
+        self.expand_aliases();
+        if let Some(name) = self.function_name() {
+            return Ok(Command::Function(self.parse_function(name)?));
+        }
+        let Some(compound) = self.parse_compound_command()? else {
+            return Ok(Command::Simple(self.parse_simple_command()?));
+        };
+        let mut redirections = Vec::new();
+        while let Some(&Token::Redirection(kind)) = self.peek() {
+            redirections.push(self.parse_redirection(kind)?);
+        }
+        Ok(Command::Compound(compound, redirections))
+    }
+
+    //Replaces an alias name at the current position with the tokens of its value, again and again
+    //while the result starts with another alias. A value ending in a blank makes the word after
+    //it a candidate too.
+    fn expand_aliases(&mut self) {
+        let mut position = self.position;
+        //Where the word following a blank-terminated value starts.
+        let mut next_word: Option<usize> = None;
+        loop {
+            let name = match self.tokens.get(position) {
+                Some(Token::Word(word)) if word.is_literal(&word.to_string()) => word.to_string(),
+                _ => String::new(),
+            };
+            let value = self.aliases.get(&name);
+            let Some(value) = value.filter(|_| !self.blocked[position].contains(&name)) else {
+                match next_word.take() {
+                    Some(next) => {
+                        position = next;
+                        continue;
+                    }
+                    None => return,
+                }
+            };
+            let Ok(expansion) = Lexer::new(value).tokenize() else {
+                return;
+            };
+            let tokens: Vec<Token> = expansion
+                .into_iter()
+                .map(|(token, _)| token)
+                .filter(|token| !matches!(token, Token::Comment(_)))
+                .collect();
+            let count = tokens.len();
+            let mut blocked = self.blocked[position].clone();
+            blocked.push(name);
+            let offset = self.offsets[position];
+            self.tokens.splice(position..=position, tokens);
+            self.offsets
+                .splice(position..=position, vec![offset; count]);
+            self.blocked
+                .splice(position..=position, vec![blocked; count]);
+            if value.ends_with([' ', '\t']) {
+                next_word = Some(position + count);
+            } else if let Some(next) = &mut next_word {
+                *next = *next + count - 1;
+            }
+        }
+    }
+
+    //Consumes `function name`, `function name()` or `name()`, the start of a function definition.
+    fn function_name(&mut self) -> Option<String> {
+        let keyword = self.at_reserved_word("function");
+        let start = self.position + usize::from(keyword);
+        let name = match self.tokens.get(start) {
+            Some(Token::Word(word)) if is_valid_name(&word.to_string()) => word.to_string(),
+            _ => return None,
+        };
+        let parens = matches!(
+            self.tokens.get(start + 1..start + 3),
+            Some([
+                Token::Operator(Operator::LeftParen),
+                Token::Operator(Operator::RightParen)
+            ])
+        );
+        if !keyword && !parens {
+            return None;
+        }
+        self.position = start + if parens { 3 } else { 1 };
+        Some(name)
+    }
+
+    //The body of a function is a compound command, possibly on the next line.
+    fn parse_function(&mut self, name: String) -> Result<FunctionDefinition, ParseError> {
+        self.skip_newlines_after_operator()?;
+        let start = self.position;
+        let body = self.parse_command()?;
+        if !matches!(body, Command::Compound(..)) {
+            self.position = start;
+            return Err(self.expected_more());
+        }
+        Ok(FunctionDefinition {
+            name,
+            body: Rc::new(body),
+        })
+    }
+
+    //A command that holds other commands, or None when the next token starts a simple command.
+    fn parse_compound_command(&mut self) -> Result<Option<CompoundCommand>, ParseError> {
+        let compound = if let Some(Token::Operator(Operator::LeftParen)) = self.peek() {
+            self.advance();
+            let body = self.parse_compound_body()?;
+            if self.peek() != Some(&Token::Operator(Operator::RightParen)) {
+                return Err(self.expected_more());
+            }
+            self.advance();
+            CompoundCommand::Subshell(body)
+        } else if self.at_reserved_word("{") {
+            self.advance();
+            let body = self.parse_compound_body()?;
+            self.expect_reserved_word("}")?;
+            CompoundCommand::BraceGroup(body)
+        } else if self.at_reserved_word("if") {
+            self.advance();
+            CompoundCommand::If(self.parse_if()?)
+        } else if self.at_reserved_word("while") || self.at_reserved_word("until") {
+            let until = self.at_reserved_word("until");
+            self.advance();
+            let condition = self.parse_compound_body()?;
+            CompoundCommand::While(WhileCommand {
+                until,
+                condition,
+                body: self.parse_do_group()?,
+            })
+        } else if self.at_reserved_word("for") {
+            self.advance();
+            self.parse_for()?
+        } else if self.at_reserved_word("case") {
+            self.advance();
+            CompoundCommand::Case(self.parse_case()?)
+        } else {
+            return Ok(None);
+        };
+        Ok(Some(compound))
+    }
+
+    //Called after the `if`.
+    fn parse_if(&mut self) -> Result<IfCommand, ParseError> {
+        let mut command = IfCommand {
+            branches: Vec::new(),
+            else_body: None,
+        };
+        loop {
+            let condition = self.parse_compound_body()?;
+            self.expect_reserved_word("then")?;
+            command
+                .branches
+                .push((condition, self.parse_compound_body()?));
+            if self.at_reserved_word("elif") {
+                self.advance();
+                continue;
+            }
+            if self.at_reserved_word("else") {
+                self.advance();
+                command.else_body = Some(self.parse_compound_body()?);
+            }
+            self.expect_reserved_word("fi")?;
+            return Ok(command);
+        }
+    }
+
+    //Called after the `for`.
+    fn parse_for(&mut self) -> Result<CompoundCommand, ParseError> {
+        if let Some(Token::Arithmetic(expression)) = self.peek() {
+            let expressions: Vec<&str> = expression.split(';').collect();
+            let [init, condition, step] = expressions.as_slice() else {
+                return Err(self.unexpected());
+            };
+            let (init, condition, step) = (
+                arithmetic_word(init)?,
+                arithmetic_word(condition)?,
+                arithmetic_word(step)?,
+            );
+            self.advance();
+            if let Some(Token::Operator(Operator::Semicolon)) = self.peek() {
+                self.advance();
+            }
+            return Ok(CompoundCommand::ArithmeticFor(ArithmeticForCommand {
+                init,
+                condition,
+                step,
+                body: self.parse_do_group()?,
+            }));
+        }
+
+        let name = match self.peek() {
+            Some(Token::Word(word)) if is_valid_name(&word.to_string()) => word.to_string(),
+            _ => return Err(self.expected_more()),
+        };
+        self.advance();
+        self.skip_newlines();
+        let mut words = None;
+        if self.at_reserved_word("in") {
+            self.advance();
+            let mut list = Vec::new();
+            while let Some(Token::Word(word)) = self.peek() {
+                list.push(word.clone());
+                self.advance();
+            }
+            words = Some(list);
+        }
+        match self.peek() {
+            Some(Token::Operator(Operator::Semicolon) | Token::Newline) => {
+                self.advance();
+            }
+            _ if words.is_some() => return Err(self.expected_more()),
+            _ => {}
+        }
+        Ok(CompoundCommand::For(ForCommand {
+            name,
+            words,
+            body: self.parse_do_group()?,
+        }))
+    }
+
+    //Called after the `case`.
+    fn parse_case(&mut self) -> Result<CaseCommand, ParseError> {
+        let Some(Token::Word(word)) = self.peek().cloned() else {
+            return Err(self.expected_more());
+        };
+        self.advance();
+        self.skip_newlines();
+        self.expect_reserved_word("in")?;
+        let mut command = CaseCommand {
+            word,
+            items: Vec::new(),
+        };
+        loop {
+            self.skip_newlines();
+            if self.at_reserved_word("esac") {
+                self.advance();
+                return Ok(command);
+            }
+            if let Some(Token::Operator(Operator::LeftParen)) = self.peek() {
+                self.advance();
+            }
+            let mut patterns = Vec::new();
+            loop {
+                let Some(Token::Word(pattern)) = self.peek().cloned() else {
+                    return Err(self.expected_more());
+                };
+                patterns.push(pattern);
+                self.advance();
+                match self.peek() {
+                    Some(Token::Operator(Operator::Pipe)) => {
+                        self.advance();
+                    }
+                    Some(Token::Operator(Operator::RightParen)) => {
+                        self.advance();
+                        break;
+                    }
+                    _ => return Err(self.expected_more()),
+                }
+            }
+            let body = self.parse_compound_list()?;
+            let terminator = match self.peek() {
+                Some(Token::Operator(Operator::DoubleSemicolon)) => CaseTerminator::Break,
+                Some(Token::Operator(Operator::SemicolonAmpersand)) => CaseTerminator::FallThrough,
+                Some(Token::Operator(Operator::DoubleSemicolonAmpersand)) => {
+                    CaseTerminator::Continue
+                }
+                //The last item can leave out its `;;`.
+                _ if self.at_reserved_word("esac") => CaseTerminator::Break,
+                _ => return Err(self.expected_more()),
+            };
+            if !self.at_reserved_word("esac") {
+                self.advance();
+            }
+            command.items.push(CaseItem {
+                patterns,
+                body,
+                terminator,
+            });
+        }
+    }
+
+    //`do list; done`, with any newlines before the `do`.
+    fn parse_do_group(&mut self) -> Result<Program, ParseError> {
+        self.skip_newlines();
+        self.expect_reserved_word("do")?;
+        let body = self.parse_compound_body()?;
+        self.expect_reserved_word("done")?;
+        Ok(body)
+    }
+
+    fn parse_redirection(&mut self, kind: Redirect) -> Result<Redirection, ParseError> {
+        self.advance();
+        let Some(Token::Word(target)) = self.advance() else {
+            self.position -= 1;
+            return Err(self.unexpected());
+        };
+        Ok(Redirection { kind, target })
+    }
+
+    fn parse_simple_command(&mut self) -> Result<SimpleCommand, ParseError> {
+        let mut command = SimpleCommand::default();
+        loop {
+            match self.peek() {
+                Some(Token::Word(word)) => {
+                    match word.as_assignment() {
+                        Some((name, value)) if command.words.is_empty() => {
+                            command.assignments.push(Assignment { name, value });
+                        }
+                        _ => command.words.push(word.clone()),
+                    }
+                    self.advance();
+                }
+                Some(&Token::Redirection(kind)) => {
+                    let redirection = self.parse_redirection(kind)?;
+                    command.redirections.push(redirection);
+                }
+                _ => break,
+            }
+        }
+        if command.assignments.is_empty()
+            && command.words.is_empty()
+            && command.redirections.is_empty()
+        {
+            return Err(self.unexpected());
+        }
+        Ok(command)
+    }
+}
@@ -0,0 +1,816 @@
+use std::fmt;
+
+use crate::parser::ast::{ParameterExpansion, ParameterModifier, ParameterOp, Word, WordPart};
+use crate::parser::command_parser::{ParseError, SyntaxError};
+use crate::utilities::redirect::Redirect;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token {
+    Word(Word),
+    Operator(Operator),
+    Redirection(Redirect),
+    //`((expression))`, kept as source text.
+    Arithmetic(String),
+    Comment(String),
+    Newline,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Pipe,
+    Semicolon,
+    DoubleSemicolon,
+    SemicolonAmpersand,
+    DoubleSemicolonAmpersand,
+    AndIf,
+    OrIf,
+    Ampersand,
+    LeftParen,
+    RightParen,
+}
+
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operator::Pipe => write!(f, "|"),
+            Operator::Semicolon => write!(f, ";"),
+            Operator::DoubleSemicolon => write!(f, ";;"),
+            Operator::SemicolonAmpersand => write!(f, ";&"),
+            Operator::DoubleSemicolonAmpersand => write!(f, ";;&"),
+            Operator::AndIf => write!(f, "&&"),
+            Operator::OrIf => write!(f, "||"),
+            Operator::Ampersand => write!(f, "&"),
+            Operator::LeftParen => write!(f, "("),
+            Operator::RightParen => write!(f, ")"),
+        }
+    }
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Word(word) => write!(f, "{}", word),
+            Token::Operator(operator) => write!(f, "{}", operator),
+            Token::Redirection(redirect) => write!(f, "{}", redirect),
+            Token::Arithmetic(expression) => write!(f, "(({}))", expression),
+            Token::Comment(text) => write!(f, "#{}", text),
+            Token::Newline => write!(f, "newline"),
+        }
+    }
+}
+
+//A peekable iterator over the input that also knows how far into it it is, for error positions.
+#[derive(Clone)]
+struct Cursor<'a> {
+    input: &'a str,
+    offset: usize,
+    peeked: Option<char>,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(input: &'a str) -> Self {
+        Cursor {
+            input,
+            offset: 0,
+            peeked: input.chars().next(),
+        }
+    }
+
+    fn peek(&self) -> Option<&char> {
+        self.peeked.as_ref()
+    }
+
+    fn next_if(&mut self, accept: impl FnOnce(&char) -> bool) -> Option<char> {
+        match self.peeked {
+            Some(c) if accept(&c) => self.next(),
+            _ => None,
+        }
+    }
+
+    fn next_if_eq(&mut self, expected: &char) -> Option<char> {
+        self.next_if(|c| c == expected)
+    }
+}
+
+impl Iterator for Cursor<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peeked?;
+        self.offset += c.len_utf8();
+        self.peeked = self.input[self.offset..].chars().next();
+        Some(c)
+    }
+}
+
+pub struct Lexer<'a> {
+    chars: Cursor<'a>,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Lexer {
+            chars: Cursor::new(input),
+        }
+    }
+
+    //A here-document's delimiter word is swapped for its body once the body has been read, so the
+    //parser sees `<<` followed by the text the command gets as stdin. Every token comes with the
+    //byte offset it starts at.
+    pub fn tokenize(mut self) -> Result<Vec<(Token, usize)>, SyntaxError> {
+        let mut tokens: Vec<(Token, usize)> = Vec::new();
+        let mut pending = Vec::new();
+        loop {
+            let offset = self.start_of_token();
+            let token = match self.next_token() {
+                Ok(Some(token)) => token,
+                Ok(None) => break,
+                Err(error) => return Err(SyntaxError { error, offset }),
+            };
+            let strip_tabs = match tokens.last() {
+                Some((Token::Redirection(Redirect::HereDoc), _)) => Some(false),
+                Some((Token::Redirection(Redirect::HereDocStripTabs), _)) => Some(true),
+                _ => None,
+            };
+            if let (Token::Word(delimiter), Some(strip_tabs)) = (&token, strip_tabs) {
+                pending.push((tokens.len(), delimiter.clone(), strip_tabs));
+            }
+            if token == Token::Newline {
+                for (index, delimiter, strip_tabs) in pending.drain(..) {
+                    let offset = tokens[index].1;
+                    let body = self
+                        .read_here_document(&delimiter, strip_tabs)
+                        .map_err(|error| SyntaxError { error, offset })?;
+                    tokens[index].0 = Token::Word(body);
+                }
+            }
+            tokens.push((token, offset));
+        }
+        if let Some((index, delimiter, _)) = pending.first() {
+            let (delimiter, _) = here_document_delimiter(delimiter);
+            return Err(SyntaxError {
+                error: ParseError::UnterminatedHereDoc(delimiter),
+                offset: tokens[*index].1,
+            });
+        }
+        Ok(tokens)
+    }
+
+    //Where the next token starts, past any blanks.
+    fn start_of_token(&mut self) -> usize {
+        while let Some(' ' | '\t') = self.chars.peek() {
+            self.chars.next();
+        }
+        self.chars.offset
+    }
+
+    fn next_token(&mut self) -> Result<Option<Token>, ParseError> {
+        self.start_of_token();
+        let Some(&next_char) = self.chars.peek() else {
+            return Ok(None);
+        };
+        let token = match next_char {
+            '\n' => {
+                self.chars.next();
+                Token::Newline
+            }
+            '#' => {
+                self.chars.next();
+                let mut comment = String::new();
+                while let Some(&c) = self.chars.peek() {
+                    if c == '\n' {
+                        break;
+                    }
+                    comment.push(c);
+                    self.chars.next();
+                }
+                Token::Comment(comment)
+            }
+            '|' => {
+                self.chars.next();
+                if self.chars.next_if_eq(&'|').is_some() {
+                    Token::Operator(Operator::OrIf)
+                } else {
+                    Token::Operator(Operator::Pipe)
+                }
+            }
+            '&' => {
+                self.chars.next();
+                if self.chars.next_if_eq(&'&').is_some() {
+                    Token::Operator(Operator::AndIf)
+                } else if self.chars.next_if_eq(&'>').is_some() {
+                    let symbol = match self.chars.next_if_eq(&'>') {
+                        Some(_) => "&>>",
+                        None => "&>",
+                    };
+                    Token::Redirection(symbol.parse().expect("`&>` and `&>>` are valid"))
+                } else {
+                    Token::Operator(Operator::Ampersand)
+                }
+            }
+            ';' => {
+                self.chars.next();
+                if self.chars.next_if_eq(&';').is_some() {
+                    if self.chars.next_if_eq(&'&').is_some() {
+                        Token::Operator(Operator::DoubleSemicolonAmpersand)
+                    } else {
+                        Token::Operator(Operator::DoubleSemicolon)
+                    }
+                } else if self.chars.next_if_eq(&'&').is_some() {
+                    Token::Operator(Operator::SemicolonAmpersand)
+                } else {
+                    Token::Operator(Operator::Semicolon)
+                }
+            }
+            '(' => {
+                self.chars.next();
+                match self.chars.peek() {
+                    Some('(') => match self.read_arithmetic_source() {
+                        Some(expression) => Token::Arithmetic(expression),
+                        None => Token::Operator(Operator::LeftParen),
+                    },
+                    _ => Token::Operator(Operator::LeftParen),
+                }
+            }
+            ')' => {
+                self.chars.next();
+                Token::Operator(Operator::RightParen)
+            }
+            '>' | '<' => self
+                .read_redirection("")
+                .expect("every operator works without an io number"),
+            _ => match self.read_word()? {
+                //Nothing but an escaped newline, which joins lines without producing a word.
+                Token::Word(word) if word.parts.is_empty() => return self.next_token(),
+                token => token,
+            },
+        };
+        Ok(Some(token))
+    }
+
+    //Called with any io number already consumed, e.g. the "2" of "2>>". None if the operator does
+    //not take that io number.
+    fn read_redirection(&mut self, io_number: &str) -> Option<Token> {
+        let mut symbol = io_number.to_string();
+        let first = self.chars.next().expect("called at a `<` or `>`");
+        symbol.push(first);
+        if first == '>' {
+            symbol.extend(self.chars.next_if(|c| matches!(c, '>' | '&')));
+        } else if let Some(second) = self.chars.next_if(|c| matches!(c, '<' | '>' | '&')) {
+            symbol.push(second);
+            //`<<<` and `<<-`.
+            if second == '<' {
+                symbol.extend(self.chars.next_if(|c| matches!(c, '<' | '-')));
+            }
+        }
+        symbol.parse().ok().map(Token::Redirection)
+    }
+
+    fn read_word(&mut self) -> Result<Token, ParseError> {
+        let parts = self.read_word_parts(is_metachar)?;
+        //Digits right before `<` or `>` name the fd to redirect, like the 2 in `2>&1`.
+        if let (Some('>' | '<'), [WordPart::Literal(digits)]) =
+            (self.chars.peek(), parts.as_slice())
+        {
+            let mut lookahead = Lexer {
+                chars: self.chars.clone(),
+            };
+            if let Some(token) = lookahead.read_redirection(digits) {
+                self.chars = lookahead.chars;
+                return Ok(token);
+            }
+        }
+        Ok(Token::Word(Word { parts }))
+    }
+
+    //Reads unquoted word text up to (not including) the first unquoted char matching `stop`.
+    fn read_word_parts(&mut self, stop: fn(char) -> bool) -> Result<Vec<WordPart>, ParseError> {
+        let mut parts = Vec::new();
+        while let Some(&c) = self.chars.peek() {
+            if stop(c) {
+                break;
+            }
+            self.chars.next();
+            match c {
+                '\\' => match self.chars.next() {
+                    Some('\n') => {}
+                    Some(escaped) => push_quoted(&mut parts, escaped),
+                    None => return Err(ParseError::UnexpectedEnd),
+                },
+                '\'' => {
+                    let mut quoted = String::new();
+                    loop {
+                        match self.chars.next() {
+                            Some('\'') => break,
+                            Some(c) => quoted.push(c),
+                            None => return Err(ParseError::UnterminatedQuote('\'')),
+                        }
+                    }
+                    parts.push(WordPart::Quoted(quoted));
+                }
+                '"' => parts.push(WordPart::DoubleQuoted(self.read_double_quoted()?)),
+                '$' if self.chars.next_if_eq(&'\'').is_some() => {
+                    parts.push(WordPart::Quoted(self.read_ansi_c_quoted()?));
+                }
+                //`$"..."` would be translated for the current locale, which always leaves it as is.
+                '$' if self.chars.next_if_eq(&'"').is_some() => {
+                    parts.push(WordPart::DoubleQuoted(self.read_double_quoted()?));
+                }
+                '$' => push_part(&mut parts, self.read_dollar()?),
+                '`' => parts.push(WordPart::CommandSubstitution(self.read_backquoted(false)?)),
+                _ => push_literal(&mut parts, c),
+            }
+        }
+        Ok(parts)
+    }
+
+    //Reads the body lines of a here-document, up to the line holding just the delimiter.
+    fn read_here_document(
+        &mut self,
+        delimiter: &Word,
+        strip_tabs: bool,
+    ) -> Result<Word, ParseError> {
+        let (delimiter, quoted) = here_document_delimiter(delimiter);
+        let mut body = String::new();
+        loop {
+            if self.chars.peek().is_none() {
+                return Err(ParseError::UnterminatedHereDoc(delimiter));
+            }
+            let mut line = String::new();
+            while let Some(c) = self.chars.next_if(|&c| c != '\n') {
+                line.push(c);
+            }
+            self.chars.next();
+            let line = if strip_tabs {
+                line.trim_start_matches('\t')
+            } else {
+                &line
+            };
+            if line == delimiter {
+                break;
+            }
+            body.push_str(line);
+            body.push('\n');
+        }
+        //With any part of the delimiter quoted the body is taken literally.
+        if quoted {
+            return Ok(Word {
+                parts: vec![WordPart::Quoted(body)],
+            });
+        }
+        let parts = Lexer::new(&body).read_here_document_body()?;
+        Ok(Word {
+            parts: vec![WordPart::DoubleQuoted(parts)],
+        })
+    }
+
+    //Like double quotes, except that `"` is an ordinary character.
+    fn read_here_document_body(&mut self) -> Result<Vec<WordPart>, ParseError> {
+        let mut parts = Vec::new();
+        while let Some(c) = self.chars.next() {
+            match c {
+                '\\' => match self.chars.peek() {
+                    Some(&escaped @ ('\\' | '$' | '`')) => {
+                        self.chars.next();
+                        push_literal(&mut parts, escaped);
+                    }
+                    Some('\n') => {
+                        self.chars.next();
+                    }
+                    _ => push_literal(&mut parts, '\\'),
+                },
+                '$' => push_part(&mut parts, self.read_dollar()?),
+                '`' => parts.push(WordPart::CommandSubstitution(self.read_backquoted(false)?)),
+                _ => push_literal(&mut parts, c),
+            }
+        }
+        Ok(parts)
+    }
+
+    //Called just after the `'` of `$'...'`. Backslash escapes are C-like and turn into the
+    //characters they stand for, e.g. `\t`, `\x41`, `\u00e9` or `\cA`.
+    fn read_ansi_c_quoted(&mut self) -> Result<String, ParseError> {
+        let mut text = String::new();
+        loop {
+            match self.chars.next() {
+                Some('\'') => return Ok(text),
+                Some('\\') => {
+                    let Some(escaped) = self.chars.next() else {
+                        break;
+                    };
+                    match escaped {
+                        'a' => text.push('\x07'),
+                        'b' => text.push('\x08'),
+                        'e' | 'E' => text.push('\x1b'),
+                        'f' => text.push('\x0c'),
+                        'n' => text.push('\n'),
+                        'r' => text.push('\r'),
+                        't' => text.push('\t'),
+                        'v' => text.push('\x0b'),
+                        '\\' | '\'' | '"' | '?' => text.push(escaped),
+                        '0'..='7' => {
+                            let mut value = escaped.to_digit(8).unwrap_or(0);
+                            for _ in 0..2 {
+                                match self.chars.peek().and_then(|c| c.to_digit(8)) {
+                                    Some(digit) => {
+                                        self.chars.next();
+                                        value = value * 8 + digit;
+                                    }
+                                    None => break,
+                                }
+                            }
+                            text.extend(char::from_u32(value & 0xff));
+                        }
+                        'x' | 'u' | 'U' => {
+                            let max_digits = match escaped {
+                                'x' => 2,
+                                'u' => 4,
+                                _ => 8,
+                            };
+                            let mut digits = String::new();
+                            while digits.len() < max_digits
+                                && let Some(c) = self.chars.next_if(char::is_ascii_hexdigit)
+                            {
+                                digits.push(c);
+                            }
+                            match u32::from_str_radix(&digits, 16)
+                                .ok()
+                                .and_then(char::from_u32)
+                            {
+                                Some(c) => text.push(c),
+                                //No digits at all: the escape is kept as written.
+                                None => {
+                                    text.push('\\');
+                                    text.push(escaped);
+                                    text.push_str(&digits);
+                                }
+                            }
+                        }
+                        'c' => match self.chars.next() {
+                            Some(c) => {
+                                text.extend(char::from_u32(c.to_ascii_uppercase() as u32 ^ 0x40))
+                            }
+                            None => break,
+                        },
+                        _ => {
+                            text.push('\\');
+                            text.push(escaped);
+                        }
+                    }
+                }
+                Some(c) => text.push(c),
+                None => break,
+            }
+        }
+        Err(ParseError::UnterminatedQuote('\''))
+    }
+
+    fn read_double_quoted(&mut self) -> Result<Vec<WordPart>, ParseError> {
+        let mut parts = Vec::new();
+        loop {
+            let Some(c) = self.chars.next() else {
+                return Err(ParseError::UnterminatedQuote('"'));
+            };
+            match c {
+                '"' => break,
+                '\\' => match self.chars.peek() {
+                    Some(&escaped @ ('"' | '\\' | '$' | '`')) => {
+                        self.chars.next();
+                        push_literal(&mut parts, escaped);
+                    }
+                    Some('\n') => {
+                        self.chars.next();
+                    }
+                    _ => push_literal(&mut parts, '\\'),
+                },
+                '$' => push_part(&mut parts, self.read_dollar()?),
+                '`' => parts.push(WordPart::CommandSubstitution(self.read_backquoted(true)?)),
+                _ => push_literal(&mut parts, c),
+            }
+        }
+        Ok(parts)
+    }
+
+    //Called just after a `$`. A `$` that starts no expansion is kept as a plain character.
+    fn read_dollar(&mut self) -> Result<WordPart, ParseError> {
+        let Some(&c) = self.chars.peek() else {
+            return Ok(WordPart::Literal("$".to_string()));
+        };
+        if c == '{' {
+            self.chars.next();
+            return self.read_braced_parameter();
+        }
+        if c == '(' {
+            self.chars.next();
+            if self.chars.peek() == Some(&'(')
+                && let Some(expression) = self.read_arithmetic()?
+            {
+                return Ok(WordPart::Arithmetic(expression));
+            }
+            return Ok(WordPart::CommandSubstitution(
+                self.read_command_substitution()?,
+            ));
+        }
+        let name = if is_special_parameter(c) || c.is_ascii_digit() {
+            self.chars.next();
+            c.to_string()
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            self.read_name()
+        } else {
+            return Ok(WordPart::Literal("$".to_string()));
+        };
+        Ok(WordPart::Parameter(ParameterExpansion {
+            name,
+            modifier: None,
+        }))
+    }
+
+    fn read_name(&mut self) -> String {
+        let mut name = String::new();
+        while let Some(c) = self
+            .chars
+            .next_if(|c| c.is_ascii_alphanumeric() || *c == '_')
+        {
+            name.push(c);
+        }
+        name
+    }
+
+    fn read_braced_parameter(&mut self) -> Result<WordPart, ParseError> {
+        let name = match self.chars.peek() {
+            Some(&c) if is_special_parameter(c) => {
+                self.chars.next();
+                c.to_string()
+            }
+            _ => self.read_name(),
+        };
+        let check_null = self.chars.next_if_eq(&':').is_some();
+        let op = match self.chars.next() {
+            Some('}') if !check_null && !name.is_empty() => {
+                return Ok(WordPart::Parameter(ParameterExpansion {
+                    name,
+                    modifier: None,
+                }));
+            }
+            Some('-') => ParameterOp::UseDefault,
+            Some('=') => ParameterOp::AssignDefault,
+            Some('?') => ParameterOp::ErrorIfUnset,
+            Some('+') => ParameterOp::UseAlternate,
+            None => return Err(ParseError::UnterminatedQuote('}')),
+            other => return Err(self.bad_substitution(name, check_null, other)),
+        };
+        if name.is_empty() {
+            return Err(self.bad_substitution(name, check_null, None));
+        }
+        let word = Word {
+            parts: self.read_word_parts(|c| c == '}')?,
+        };
+        if self.chars.next().is_none() {
+            return Err(ParseError::UnterminatedQuote('}'));
+        }
+        Ok(WordPart::Parameter(ParameterExpansion {
+            name,
+            modifier: Some(ParameterModifier {
+                op,
+                check_null,
+                word,
+            }),
+        }))
+    }
+
+    //Called on the second `(` of `$((`. Falls back to `None`, consuming nothing, when there is no
+    //matching `))`, as `$((cmd) | (cmd))` is a command substitution instead.
+    fn read_arithmetic(&mut self) -> Result<Option<Word>, ParseError> {
+        match self.read_arithmetic_source() {
+            Some(expression) => Ok(Some(arithmetic_word(&expression)?)),
+            None => Ok(None),
+        }
+    }
+
+    //The text between `((` and the matching `))`, called on the second `(`.
+    fn read_arithmetic_source(&mut self) -> Option<String> {
+        let mut lookahead = self.chars.clone();
+        lookahead.next();
+        let mut expression = String::new();
+        let mut depth = 0;
+        loop {
+            match lookahead.next() {
+                Some(')') if depth == 0 => {
+                    if lookahead.next_if_eq(&')').is_some() {
+                        break;
+                    }
+                    return None;
+                }
+                Some(c) => {
+                    match c {
+                        '(' => depth += 1,
+                        ')' => depth -= 1,
+                        _ => {}
+                    }
+                    expression.push(c);
+                }
+                None => return None,
+            }
+        }
+        self.chars = lookahead;
+        Some(expression)
+    }
+
+    //Collects the source of `$(...)` up to its matching `)`, stepping over quoted text and comments.
+    fn read_command_substitution(&mut self) -> Result<String, ParseError> {
+        let mut source = String::new();
+        let mut depth = 0;
+        let mut at_word_start = true;
+        loop {
+            let Some(c) = self.chars.next() else {
+                return Err(ParseError::UnterminatedQuote(')'));
+            };
+            match c {
+                ')' if depth == 0 => break,
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                '\\' => {
+                    source.push(c);
+                    source.extend(self.chars.next());
+                    at_word_start = false;
+                    continue;
+                }
+                '\'' => {
+                    //Backslashes only escape inside `$'...'`.
+                    let escapes = source.ends_with('$');
+                    source.push(c);
+                    self.copy_until(&mut source, '\'', escapes);
+                    at_word_start = false;
+                    continue;
+                }
+                '"' => {
+                    source.push(c);
+                    self.copy_double_quoted(&mut source)?;
+                    at_word_start = false;
+                    continue;
+                }
+                '`' => {
+                    source.push(c);
+                    self.copy_until(&mut source, '`', true);
+                    at_word_start = false;
+                    continue;
+                }
+                '#' if at_word_start => {
+                    source.push(c);
+                    while let Some(c) = self.chars.next_if(|&c| c != '\n') {
+                        source.push(c);
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+            source.push(c);
+            at_word_start = c.is_whitespace() || matches!(c, ';' | '&' | '|' | '(' | ')');
+        }
+        Ok(source)
+    }
+
+    //Copies text up to and including `end`, honouring backslash escapes on the way if asked to.
+    fn copy_until(&mut self, source: &mut String, end: char, escapes: bool) {
+        while let Some(c) = self.chars.next() {
+            source.push(c);
+            if c == end {
+                return;
+            }
+            if c == '\\' && escapes {
+                source.extend(self.chars.next());
+            }
+        }
+    }
+
+    fn copy_double_quoted(&mut self, source: &mut String) -> Result<(), ParseError> {
+        while let Some(c) = self.chars.next() {
+            source.push(c);
+            match c {
+                '"' => return Ok(()),
+                '\\' => source.extend(self.chars.next()),
+                '`' => self.copy_until(source, '`', true),
+                '$' if self.chars.next_if_eq(&'(').is_some() => {
+                    source.push('(');
+                    source.push_str(&self.read_command_substitution()?);
+                    source.push(')');
+                }
+                _ => {}
+            }
+        }
+        Err(ParseError::UnterminatedQuote('"'))
+    }
+
+    //The old `cmd` form, where a backslash only escapes `$`, `\` and backquote (and `"` inside double quotes).
+    fn read_backquoted(&mut self, in_dquote: bool) -> Result<String, ParseError> {
+        let mut source = String::new();
+        loop {
+            let Some(c) = self.chars.next() else {
+                return Err(ParseError::UnterminatedQuote('`'));
+            };
+            match c {
+                '`' => break,
+                '\\' => match self.chars.next() {
+                    Some(escaped @ ('$' | '`' | '\\')) => source.push(escaped),
+                    Some('"') if in_dquote => source.push('"'),
+                    Some(other) => {
+                        source.push('\\');
+                        source.push(other);
+                    }
+                    None => source.push('\\'),
+                },
+                _ => source.push(c),
+            }
+        }
+        Ok(source)
+    }
+
+    //Rebuilds the offending `${...}` text for the error message.
+    fn bad_substitution(
+        &mut self,
+        name: String,
+        check_null: bool,
+        after: Option<char>,
+    ) -> ParseError {
+        let mut text = format!("${{{}", name);
+        if check_null {
+            text.push(':');
+        }
+        text.extend(after);
+        if after != Some('}') {
+            for c in self.chars.by_ref() {
+                text.push(c);
+                if c == '}' {
+                    break;
+                }
+            }
+        }
+        ParseError::BadSubstitution(text)
+    }
+}
+
+//An arithmetic expression as a word, so parameters and substitutions in it get expanded first.
+pub fn arithmetic_word(expression: &str) -> Result<Word, ParseError> {
+    let parts = Lexer::new(expression).read_word_parts(|_| false)?;
+    Ok(Word { parts })
+}
+
+fn is_metachar(c: char) -> bool {
+    matches!(
+        c,
+        ' ' | '\t' | '\n' | '|' | '&' | ';' | '(' | ')' | '>' | '<'
+    )
+}
+
+//The delimiter text after quote removal, and whether any of it was quoted.
+fn here_document_delimiter(word: &Word) -> (String, bool) {
+    let mut text = String::new();
+    let mut quoted = false;
+    for part in &word.parts {
+        match part {
+            WordPart::Literal(literal) => text.push_str(literal),
+            WordPart::Quoted(literal) => {
+                text.push_str(literal);
+                quoted = true;
+            }
+            WordPart::DoubleQuoted(inner) => {
+                text.push_str(
+                    &here_document_delimiter(&Word {
+                        parts: inner.clone(),
+                    })
+                    .0,
+                );
+                quoted = true;
+            }
+            part => text.push_str(&part.to_string()),
+        }
+    }
+    (text, quoted)
+}
+
+fn is_special_parameter(c: char) -> bool {
+    matches!(c, '?' | '$' | '#' | '@' | '*' | '!' | '-' | '0')
+}
+
+fn push_part(parts: &mut Vec<WordPart>, part: WordPart) {
+    match part {
+        WordPart::Literal(text) => text.chars().for_each(|c| push_literal(parts, c)),
+        part => parts.push(part),
+    }
+}
+
+fn push_literal(parts: &mut Vec<WordPart>, c: char) {
+    if let Some(WordPart::Literal(text)) = parts.last_mut() {
+        text.push(c);
+    } else {
+        parts.push(WordPart::Literal(c.to_string()));
+    }
+}
+
+fn push_quoted(parts: &mut Vec<WordPart>, c: char) {
+    if let Some(WordPart::Quoted(text)) = parts.last_mut() {
+        text.push(c);
+    } else {
+        parts.push(WordPart::Quoted(c.to_string()));
+    }
+}
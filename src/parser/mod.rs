@@ -0,0 +1,3 @@
+pub mod ast;
+pub mod command_parser;
+pub mod lexer;
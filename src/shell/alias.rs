@@ -0,0 +1,80 @@
+use std::io::{self, Write};
+
+use crate::executor::state::ShellState;
+use crate::executor::streams::{Stream, Streams};
+
+//`alias` lists every alias, `alias name` shows one and `alias name=value` defines one.
+pub fn alias_command(
+    state: &mut ShellState,
+    args: &[String],
+    streams: &mut Streams,
+) -> io::Result<i32> {
+    if args.is_empty() {
+        let mut names: Vec<&String> = state.aliases.keys().collect();
+        names.sort();
+        for name in names {
+            print_alias(streams.stdout(), name, &state.aliases[name])?;
+        }
+        return Ok(0);
+    }
+    let mut status = 0;
+    for arg in args {
+        match arg.split_once('=') {
+            Some((name, value)) if is_valid_alias_name(name) => {
+                state.aliases.insert(name.to_string(), value.to_string());
+            }
+            Some((name, _)) => {
+                writeln!(streams.stderr(), "alias: `{}': invalid alias name", name)?;
+                status = 1;
+            }
+            None => match state.aliases.get(arg) {
+                Some(value) => print_alias(streams.stdout(), arg, value)?,
+                None => {
+                    writeln!(streams.stderr(), "alias: {}: not found", arg)?;
+                    status = 1;
+                }
+            },
+        }
+    }
+    Ok(status)
+}
+
+//`unalias name...` removes aliases, `unalias -a` removes them all.
+pub fn unalias_command(
+    state: &mut ShellState,
+    args: &[String],
+    streams: &mut Streams,
+) -> io::Result<i32> {
+    if args.is_empty() {
+        writeln!(
+            streams.stderr(),
+            "unalias: usage: unalias [-a] name [name ...]"
+        )?;
+        return Ok(2);
+    }
+    if args.iter().any(|arg| arg == "-a") {
+        state.aliases.clear();
+        return Ok(0);
+    }
+    let mut status = 0;
+    for name in args {
+        if state.aliases.remove(name).is_none() {
+            writeln!(streams.stderr(), "unalias: {}: not found", name)?;
+            status = 1;
+        }
+    }
+    Ok(status)
+}
+
+//Printed in a form that can be typed back in.
+fn print_alias(stdout: &mut Stream, name: &str, value: &str) -> io::Result<()> {
+    writeln!(stdout, "alias {}='{}'", name, value.replace('\'', "'\\''"))
+}
+
+//Anything the lexer reads back as a single plain word.
+fn is_valid_alias_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.contains([
+            '/', '$', '`', '=', '\\', '\'', '"', ' ', '\t', '\n', '|', '&', ';', '(', ')', '<', '>',
+        ])
+}
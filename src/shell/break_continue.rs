@@ -0,0 +1,62 @@
+use std::io::{self, Write};
+
+use crate::executor::state::{Jump, ShellState};
+use crate::executor::streams::Streams;
+
+pub fn break_command(
+    state: &mut ShellState,
+    args: &[String],
+    streams: &mut Streams,
+) -> io::Result<i32> {
+    jump_command(state, "break", args, streams, Jump::Break)
+}
+
+pub fn continue_command(
+    state: &mut ShellState,
+    args: &[String],
+    streams: &mut Streams,
+) -> io::Result<i32> {
+    jump_command(state, "continue", args, streams, Jump::Continue)
+}
+
+//`break N` and `continue N` act on the Nth enclosing loop, or the outermost one if there are fewer.
+fn jump_command(
+    state: &mut ShellState,
+    name: &str,
+    args: &[String],
+    streams: &mut Streams,
+    jump: fn(usize) -> Jump,
+) -> io::Result<i32> {
+    if state.loop_depth == 0 {
+        writeln!(
+            streams.stderr(),
+            "{}: only meaningful in a `for', `while', or `until' loop",
+            name
+        )?;
+        return Ok(0);
+    }
+    let levels = match args.first().map(|arg| (arg, arg.parse::<i64>())) {
+        None => 1,
+        Some((_, Ok(levels))) if levels > 0 => levels as usize,
+        Some((arg, Ok(_))) => {
+            writeln!(
+                streams.stderr(),
+                "{}: {}: loop count out of range",
+                name,
+                arg
+            )?;
+            return Ok(1);
+        }
+        Some((arg, Err(_))) => {
+            writeln!(
+                streams.stderr(),
+                "{}: {}: numeric argument required",
+                name,
+                arg
+            )?;
+            return Ok(1);
+        }
+    };
+    state.jump = Some(jump(levels.min(state.loop_depth)));
+    Ok(0)
+}
@@ -1,18 +1,30 @@
+use std::io::{self, Write};
+
 use pathsearch::find_executable_in_path;
 
-use crate::{BUILTIN_COMMANDS, utilities::input_parser::input_line_parsing};
+use crate::BUILTIN_COMMANDS;
+use crate::executor::state::ShellState;
+use crate::executor::streams::Streams;
+use crate::parser::command_parser::RESERVED_WORDS;
 
-pub fn type_command(input: &str) {
-    let full_parsed_command = input_line_parsing(input);
-    let args = &full_parsed_command[1];
-    if !args.is_empty() && BUILTIN_COMMANDS.contains(&args.as_str()) {
-        print!("{} is a shell builtin\n", args);
-        return;
-    } else if !args.is_empty() {
-        if let Some(path) = find_executable_in_path(&args) {
-            print!("{} is {}\n", args, path.display());
-            return;
+pub fn type_command(state: &ShellState, args: &[String], streams: &mut Streams) -> io::Result<i32> {
+    let mut status = 0;
+    for name in args {
+        if let Some(value) = state.aliases.get(name) {
+            writeln!(streams.stdout(), "{} is aliased to '{}'", name, value)?;
+        } else if RESERVED_WORDS.contains(&name.as_str()) {
+            writeln!(streams.stdout(), "{} is a shell keyword", name)?;
+        } else if BUILTIN_COMMANDS.contains(&name.as_str()) {
+            writeln!(streams.stdout(), "{} is a shell builtin", name)?;
+        } else if let Some(body) = state.functions.get(name) {
+            writeln!(streams.stdout(), "{} is a function", name)?;
+            writeln!(streams.stdout(), "{} ()\n{}", name, body)?;
+        } else if let Some(path) = find_executable_in_path(name) {
+            writeln!(streams.stdout(), "{} is {}", name, path.display())?;
+        } else {
+            writeln!(streams.stderr(), "{}: not found", name)?;
+            status = 1;
         }
     }
-    print!("{}: not found\n", args);
+    Ok(status)
 }
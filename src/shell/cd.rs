@@ -1,25 +1,43 @@
+use std::io::{self, Write};
 use std::{env, path::Path};
 
-pub fn cd_command(ab_path: &str) {
-    if ab_path == "~" {
-        let home_dir = env::home_dir();
-        match home_dir {
-            Some(home_dir_path) => {
-                let home_dir_path_string = home_dir_path.display().to_string();
-                let cd_path = Path::new(&home_dir_path_string);
-                match env::set_current_dir(&cd_path) {
-                    Ok(_) => {}
-                    Err(_) => print!("cd: can't get to home directory\n"),
-                }
+use crate::executor::state::ShellState;
+use crate::executor::streams::Streams;
+
+pub fn cd_command(
+    state: &mut ShellState,
+    args: &[String],
+    streams: &mut Streams,
+) -> io::Result<i32> {
+    let ab_path = match args.first() {
+        Some(ab_path) => ab_path.clone(),
+        None => match state.get_var("HOME") {
+            Some(home) => home.to_string(),
+            None => {
+                writeln!(streams.stderr(), "cd: can't get to home directory")?;
+                return Ok(1);
+            }
+        },
+    };
+    let cd_path = Path::new(&ab_path);
+    let is_path = env::set_current_dir(cd_path);
+    match is_path {
+        Ok(_) => {
+            if let Some(old_pwd) = state.get_var("PWD").map(str::to_string) {
+                state.set_var("OLDPWD", old_pwd);
+            }
+            if let Ok(current_path) = env::current_dir() {
+                state.set_var("PWD", current_path.display().to_string());
             }
-            None => print!("cd: can't get to home directory\n"),
+            Ok(0)
         }
-    } else {
-        let cd_path = Path::new(ab_path);
-        let is_path = env::set_current_dir(&cd_path);
-        match is_path {
-            Ok(_) => {}
-            Err(_) => print!("cd: {}: No such file or directory\n", ab_path),
+        Err(_) => {
+            writeln!(
+                streams.stderr(),
+                "cd: {}: No such file or directory",
+                ab_path
+            )?;
+            Ok(1)
         }
     }
 }
@@ -0,0 +1,21 @@
+use crate::executor::state::ShellState;
+use crate::utilities::history::read_history_file;
+use crate::utilities::redirect::handle_redirect;
+
+pub fn exit_command(state: &ShellState, args: &[String]) -> ! {
+    //Only an interactive shell saves history, and only the shell itself, not a subshell or
+    //pipeline stage leaving early.
+    if state.interactive
+        && std::process::id() == state.shell_pid
+        && let Ok(histfile) = std::env::var("HISTFILE")
+        && let Some(contents) = read_history_file()
+        && let Err(err) = handle_redirect(&histfile, contents.as_bytes())
+    {
+        eprintln!("exit: {}: {}", histfile, err);
+    }
+    let code = args
+        .first()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(state.last_status);
+    std::process::exit(code);
+}
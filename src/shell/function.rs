@@ -0,0 +1,63 @@
+use std::io::{self, Write};
+
+use crate::executor::state::{Jump, ShellState};
+use crate::executor::streams::Streams;
+use crate::parser::ast::is_valid_name;
+
+//`local name` or `local name=value`, only allowed inside a function.
+pub fn local_command(
+    state: &mut ShellState,
+    args: &[String],
+    streams: &mut Streams,
+) -> io::Result<i32> {
+    if state.locals.is_empty() {
+        writeln!(streams.stderr(), "local: can only be used in a function")?;
+        return Ok(1);
+    }
+    let mut status = 0;
+    for arg in args {
+        let (name, value) = match arg.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (arg.as_str(), None),
+        };
+        if !is_valid_name(name) {
+            writeln!(streams.stderr(), "local: `{}': not a valid identifier", arg)?;
+            status = 1;
+            continue;
+        }
+        state.declare_local(name);
+        if let Some(value) = value {
+            state.set_var(name, value.to_string());
+        }
+    }
+    Ok(status)
+}
+
+//`return N` leaves the running function with status N, or the last status without an argument.
+pub fn return_command(
+    state: &mut ShellState,
+    args: &[String],
+    streams: &mut Streams,
+) -> io::Result<i32> {
+    if state.locals.is_empty() {
+        writeln!(
+            streams.stderr(),
+            "return: can only `return' from a function"
+        )?;
+        return Ok(1);
+    }
+    let status = match args.first().map(|arg| (arg, arg.parse::<i64>())) {
+        None => state.last_status,
+        Some((_, Ok(status))) => (status & 0xff) as i32,
+        Some((arg, Err(_))) => {
+            writeln!(
+                streams.stderr(),
+                "return: {}: numeric argument required",
+                arg
+            )?;
+            2
+        }
+    };
+    state.jump = Some(Jump::Return(status));
+    Ok(status)
+}
@@ -1,91 +1,87 @@
 use std::fs::{self, File};
-use std::io::{BufReader, Read};
+use std::io::{self, BufReader, Read, Write};
 
-use crate::utilities::input_parser::input_line_parsing;
+use crate::executor::streams::Streams;
+
+use crate::utilities::history::{import_history, read_history_entries, read_history_file};
 use crate::utilities::redirect::{handle_redirect, handle_redirect_append};
 
-pub fn history_command(input: &str) {
-    let parsed_history_commands = input_line_parsing(input);
-    let args = &parsed_history_commands[1..];
+pub fn history_command(args: &[String], streams: &mut Streams) -> io::Result<i32> {
     let outer_file = File::open("history.txt");
     match outer_file {
-        Ok(outer_file) => {
-            let mut reader = BufReader::new(outer_file);
-            let mut outer_contents = String::new();
-            reader.read_to_string(&mut outer_contents).unwrap();
-            let lines: Vec<String> = outer_contents
-                .trim()
-                .lines()
-                .map(|l| l.to_string())
-                .collect();
+        Ok(_) => {
+            let lines = read_history_entries();
 
             if args.is_empty() {
                 for (i, line) in lines.iter().enumerate() {
-                    println!("    {} {}", i + 1, line);
+                    writeln!(streams.stdout(), "    {} {}", i + 1, line)?;
                 }
-                return;
+                return Ok(0);
             }
 
             match args[0].as_str() {
                 "-r" => {
                     if args.len() == 1 {
-                        return;
+                        return Ok(0);
                     }
                     let file = File::open(&args[1]);
                     match file {
                         Ok(file) => {
                             let mut contents = String::new();
                             let mut buf_read = BufReader::new(file);
-                            buf_read.read_to_string(&mut contents).unwrap();
-                            let temp = "history.txt".to_string();
-                            handle_redirect_append(&temp, contents.as_bytes());
+                            if let Err(err) = buf_read.read_to_string(&mut contents) {
+                                writeln!(streams.stderr(), "history: {}: {}", args[1], err)?;
+                                return Ok(1);
+                            }
+                            if let Err(err) = import_history(&contents) {
+                                writeln!(streams.stderr(), "history: history.txt: {}", err)?;
+                                return Ok(1);
+                            }
                         }
                         Err(_) => {
-                            eprintln!("Cannot open file");
-                            return;
+                            writeln!(streams.stderr(), "Cannot open file")?;
+                            return Ok(1);
                         }
                     }
+                    Ok(0)
                 }
                 "-w" => {
                     if args.len() == 1 {
-                        return;
+                        return Ok(0);
                     }
-                    let file = File::open("history.txt");
-                    match file {
-                        Ok(file) => {
-                            let mut contents = String::new();
-                            let mut buf_read = BufReader::new(file);
-                            buf_read.read_to_string(&mut contents).unwrap();
-                            handle_redirect(&args[1], contents.as_bytes());
+                    match read_history_file() {
+                        Some(contents) => {
+                            if let Err(err) = handle_redirect(&args[1], contents.as_bytes()) {
+                                writeln!(streams.stderr(), "history: {}: {}", args[1], err)?;
+                                return Ok(1);
+                            }
                         }
-                        Err(_) => {
-                            eprintln!("Cannot open file");
-                            return;
+                        None => {
+                            writeln!(streams.stderr(), "Cannot open file")?;
+                            return Ok(1);
                         }
                     }
+                    Ok(0)
                 }
                 "-a" => {
                     if args.len() == 1 {
-                        return;
+                        return Ok(0);
                     }
-                    let file = File::open("history.txt");
-                    match file {
-                        Ok(file) => {
-                            let mut contents = String::new();
-                            let mut buf_read = BufReader::new(file);
-                            buf_read.read_to_string(&mut contents).unwrap();
-                            handle_redirect_append(&args[1], contents.as_bytes());
+                    match read_history_file() {
+                        Some(contents) => {
+                            if let Err(err) = handle_redirect_append(&args[1], contents.as_bytes())
+                            {
+                                writeln!(streams.stderr(), "history: {}: {}", args[1], err)?;
+                                return Ok(1);
+                            }
                         }
-                        Err(_) => {
-                            eprintln!("Cannot open file");
-                            return;
+                        None => {
+                            writeln!(streams.stderr(), "Cannot open file")?;
+                            return Ok(1);
                         }
                     }
-                    let remove = fs::remove_file("history.txt");
-                    match remove {
-                        Ok(remove) => remove,
-                        Err(_) => {}
-                    }
+                    let _ = fs::remove_file("history.txt");
+                    Ok(0)
                 }
                 val => match val.parse::<usize>() {
                     Ok(n) => {
@@ -93,21 +89,25 @@ pub fn history_command(input: &str) {
                             let mut strt_idx = start_line;
                             for line in lines[start_line..].iter() {
                                 strt_idx += 1;
-                                println!("    {} {}", strt_idx, line);
+                                writeln!(streams.stdout(), "    {} {}", strt_idx, line)?;
                             }
                         } else {
                             for (i, line) in lines.iter().enumerate() {
-                                println!("    {} {}", i + 1, line);
+                                writeln!(streams.stdout(), "    {} {}", i + 1, line)?;
                             }
-                            return;
-                        };
+                        }
+                        Ok(0)
                     }
                     Err(_) => {
-                        eprintln!("Unknown argument: {}", val);
+                        writeln!(streams.stderr(), "Unknown argument: {}", val)?;
+                        Ok(1)
                     }
                 },
             }
         }
-        Err(_) => eprint!("cant open file"),
+        Err(_) => {
+            write!(streams.stderr(), "cant open file")?;
+            Ok(1)
+        }
     }
 }
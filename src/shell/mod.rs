@@ -1,6 +1,11 @@
+pub mod alias;
+pub mod break_continue;
 pub mod c_type;
 pub mod cd;
 pub mod diff_command;
 pub mod echo;
+pub mod exit;
+pub mod function;
 pub mod history;
 pub mod pwd;
+pub mod read;
@@ -1,9 +1,18 @@
 use std::env;
+use std::io::{self, Write};
 
-pub fn pwd_command() {
+use crate::executor::streams::Streams;
+
+pub fn pwd_command(streams: &mut Streams) -> io::Result<i32> {
     let current_path = env::current_dir();
     match current_path {
-        Ok(path) => print!("{}\n", path.display()),
-        Err(_) => print!("\n"),
+        Ok(path) => {
+            writeln!(streams.stdout(), "{}", path.display())?;
+            Ok(0)
+        }
+        Err(_) => {
+            writeln!(streams.stdout())?;
+            Ok(1)
+        }
     }
 }
@@ -0,0 +1,65 @@
+use std::io::{self, Read, Write};
+
+use crate::executor::state::ShellState;
+use crate::executor::streams::{Stream, Streams};
+use crate::parser::ast::is_valid_name;
+
+//`read [-r] [name...]` takes one line of stdin. Each name gets a word and the last one gets the
+//rest of the line; without names the whole line goes to REPLY. Fails at end of input.
+pub fn read_command(
+    state: &mut ShellState,
+    args: &[String],
+    streams: &mut Streams,
+) -> io::Result<i32> {
+    let raw = args.first().is_some_and(|arg| arg == "-r");
+    let names = if raw { &args[1..] } else { args };
+    if let Some(name) = names.iter().find(|name| !is_valid_name(name)) {
+        writeln!(streams.stderr(), "read: `{}': not a valid identifier", name)?;
+        return Ok(1);
+    }
+
+    let (line, complete) = read_line(streams.stdin(), raw);
+    let line = String::from_utf8_lossy(&line);
+    if names.is_empty() {
+        state.set_var("REPLY", line.into_owned());
+        return Ok(if complete { 0 } else { 1 });
+    }
+    let mut rest = line.trim_matches([' ', '\t']);
+    for (index, name) in names.iter().enumerate() {
+        let value = if index == names.len() - 1 {
+            rest
+        } else {
+            let end = rest.find([' ', '\t']).unwrap_or(rest.len());
+            let (word, after) = rest.split_at(end);
+            rest = after.trim_start_matches([' ', '\t']);
+            word
+        };
+        state.set_var(name, value.to_string());
+    }
+    Ok(if complete { 0 } else { 1 })
+}
+
+//Reads a byte at a time so nothing past the newline is taken from a stdin shared with other
+//commands. Without -r a backslash escapes the next character and joins continued lines.
+fn read_line(stdin: &mut Stream, raw: bool) -> (Vec<u8>, bool) {
+    let mut line = Vec::new();
+    let mut escaped = false;
+    loop {
+        let mut byte = [0u8];
+        match stdin.read(&mut byte) {
+            Ok(1) => {}
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            _ => return (line, false),
+        }
+        let [byte] = byte;
+        match byte {
+            b'\n' if escaped => escaped = false,
+            b'\n' => return (line, true),
+            b'\\' if !raw && !escaped => escaped = true,
+            _ => {
+                escaped = false;
+                line.push(byte);
+            }
+        }
+    }
+}
@@ -3,13 +3,78 @@ use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
 use std::io::{self, Write};
 
 use crate::auto_complete;
+use crate::parser::command_parser::{ParseError, SyntaxError, parse_input};
 use crate::terminal::draw_line::redraw_entire_line;
 use crate::utilities::executable::print_all_exec;
-use crate::utilities::history::fetch_history_commands;
+use crate::utilities::history::{append_history_entry, fetch_history_commands};
 use crate::utilities::longest_prefix::longest_common_prefix;
-use crate::utilities::redirect::handle_redirect_append;
 
-pub fn read_inputs_keypress() -> String {
+//Reads lines until they make up a whole command, showing `continuation_prompt` (PS2) for every
+//line after the first. The command goes into history as one entry. `aliases` are offered by Tab
+//completion along with the builtins.
+pub fn read_command_input(continuation_prompt: &str, aliases: &[&str]) -> String {
+    'command: loop {
+        print!("\r$ ");
+        io::stdout().flush().unwrap();
+        let Some(mut input) = read_inputs_keypress("$ ", aliases) else {
+            continue;
+        };
+        let mut history_entry = input.clone();
+        while let Err(SyntaxError { error, .. }) = parse_input(&input)
+            && error.is_incomplete()
+        {
+            print!("\r{}", continuation_prompt);
+            io::stdout().flush().unwrap();
+            let Some(line) = read_inputs_keypress(continuation_prompt, aliases) else {
+                continue 'command;
+            };
+            //Joined the way bash does: escaped newlines vanish and other line breaks between
+            //commands become `; `. Only newlines inside quotes and here-documents are kept.
+            match error {
+                ParseError::UnexpectedEnd if history_entry.ends_with('\\') => {
+                    history_entry.pop();
+                }
+                ParseError::UnexpectedEnd if line.is_empty() => {}
+                ParseError::UnexpectedEnd => {
+                    history_entry.push_str(history_separator(&history_entry))
+                }
+                _ => history_entry.push('\n'),
+            }
+            history_entry.push_str(&line);
+            input.push('\n');
+            input.push_str(&line);
+        }
+        if !history_entry.is_empty()
+            && let Err(err) = append_history_entry(&history_entry)
+        {
+            eprintln!("myshell: history.txt: {}", err);
+        }
+        return input;
+    }
+}
+
+//A line ending in an operator or in a keyword like `then` just goes on, anything else ended a command.
+fn history_separator(entry: &str) -> &'static str {
+    const CONTINUED_BY: &[&str] = &[
+        "if", "then", "elif", "else", "while", "until", "do", "in", "{",
+    ];
+    let entry = entry.trim_end();
+    let mut words = entry.rsplit([' ', '\t', '\n', ';']);
+    let last_word = words.next().unwrap_or("");
+    //`function name` is still waiting for its body.
+    let defining_function = words.next() == Some("function");
+    if entry.ends_with(['|', '&', ';', '(', ')', '\n'])
+        || CONTINUED_BY.contains(&last_word)
+        || defining_function
+    {
+        " "
+    } else {
+        "; "
+    }
+}
+
+//Reads one line, or `None` when it is thrown away with Ctrl-C.
+fn read_inputs_keypress(prompt: &str, aliases: &[&str]) -> Option<String> {
     enable_raw_mode().unwrap();
     let mut buffer = String::new();
     let mut tab_flip = false;
@@ -35,9 +100,6 @@ pub fn read_inputs_keypress() -> String {
                     ..
                 } => {
                     print!("\r\n");
-                    if !buffer.is_empty() {
-                        handle_redirect_append(&String::from("history.txt"), buffer.as_bytes());
-                    }
                     io::stdout().flush().unwrap();
                     break;
                 }
@@ -45,13 +107,11 @@ pub fn read_inputs_keypress() -> String {
                 KeyEvent {
                     code: KeyCode::Backspace,
                     ..
-                } => {
-                    if !buffer.is_empty() {
-                        tab_flip = false;
-                        buffer.pop();
-                        print!("\x1b[D\x1b[K");
-                        io::stdout().flush().unwrap();
-                    }
+                } if !buffer.is_empty() => {
+                    tab_flip = false;
+                    buffer.pop();
+                    print!("\x1b[D\x1b[K");
+                    io::stdout().flush().unwrap();
                 }
 
                 KeyEvent {
@@ -60,7 +120,7 @@ pub fn read_inputs_keypress() -> String {
                     history_index += 1;
                     if let Some(his_buffer) = fetch_history_commands(history_index) {
                         buffer = his_buffer;
-                        redraw_entire_line("$ ", &buffer);
+                        redraw_entire_line(prompt, &buffer);
                     } else {
                         history_index -= 1;
                     }
@@ -78,7 +138,7 @@ pub fn read_inputs_keypress() -> String {
                     } else if let Some(his_buffer) = fetch_history_commands(history_index) {
                         buffer = his_buffer;
                     }
-                    redraw_entire_line("$ ", &buffer);
+                    redraw_entire_line(prompt, &buffer);
                 }
 
                 KeyEvent {
@@ -86,22 +146,22 @@ pub fn read_inputs_keypress() -> String {
                 } => {
                     if buffer.is_empty() {
                         println!("\x07");
-                        redraw_entire_line("$ ", &buffer);
+                        redraw_entire_line(prompt, &buffer);
                         tab_flip = false;
                         continue;
                     }
-                    let (string_option, string_vector) = auto_complete(&buffer);
+                    let (string_option, string_vector) = auto_complete(&buffer, aliases);
                     match string_vector.len() {
                         0 => {
                             println!("\x07");
-                            redraw_entire_line("$ ", &buffer);
+                            redraw_entire_line(prompt, &buffer);
                             tab_flip = false;
                         }
                         1 => {
                             if let Some(complete_command) = string_option {
                                 buffer = complete_command;
                                 buffer.push(' ');
-                                redraw_entire_line("$ ", &buffer);
+                                redraw_entire_line(prompt, &buffer);
                                 tab_flip = false;
                             }
                         }
@@ -109,17 +169,17 @@ pub fn read_inputs_keypress() -> String {
                             let common = longest_common_prefix(&string_vector);
                             if common.len() > buffer.len() {
                                 buffer = common;
-                                redraw_entire_line("$ ", &buffer);
+                                redraw_entire_line(prompt, &buffer);
                                 tab_flip = false;
                             } else {
-                                if tab_flip == false {
+                                if !tab_flip {
                                     print!("\x07");
-                                    redraw_entire_line("$ ", &buffer);
+                                    redraw_entire_line(prompt, &buffer);
                                     tab_flip = true;
                                 } else {
                                     print!("\r\n");
                                     print_all_exec(string_vector);
-                                    redraw_entire_line("$ ", &buffer);
+                                    redraw_entire_line(prompt, &buffer);
                                     tab_flip = false;
                                 }
                             }
@@ -143,11 +203,9 @@ pub fn read_inputs_keypress() -> String {
                     modifiers: KeyModifiers::CONTROL,
                     ..
                 } => {
-                    tab_flip = false;
                     print!("^C\r\n");
-                    buffer.clear();
-                    redraw_entire_line("$ ", &buffer);
-                    history_index = 0;
+                    disable_raw_mode().unwrap();
+                    return None;
                 }
 
                 _ => {}
@@ -155,5 +213,5 @@ pub fn read_inputs_keypress() -> String {
         }
     }
     disable_raw_mode().unwrap();
-    return buffer;
+    Some(buffer)
 }
@@ -4,12 +4,12 @@ use std::{env, fs};
 use std::os::unix::fs::PermissionsExt;
 
 pub fn get_path_directories() -> Vec<std::path::PathBuf> {
-    return env::var_os("PATH")
+    env::var_os("PATH")
         .unwrap_or_default()
         .to_string_lossy()
         .split(':')
         .map(std::path::PathBuf::from)
-        .collect();
+        .collect()
 }
 
 pub fn find_executable(char_slice: &str) -> Vec<String> {
@@ -18,17 +18,18 @@ pub fn find_executable(char_slice: &str) -> Vec<String> {
         if let Ok(entries) = fs::read_dir(dir) {
             for entry in entries.flatten() {
                 let path = entry.path();
-                if let Some(execname) = path.file_name().and_then(|n| n.to_str()) {
-                    if execname.starts_with(char_slice) && is_executable(&path) {
-                        results.push(execname.to_string());
-                    }
+                if let Some(execname) = path.file_name().and_then(|n| n.to_str())
+                    && execname.starts_with(char_slice)
+                    && is_executable(&path)
+                {
+                    results.push(execname.to_string());
                 }
             }
         }
     }
     results.sort();
     results.dedup();
-    return results;
+    results
 }
 
 pub fn is_executable(path: &std::path::Path) -> bool {
@@ -1,19 +1,55 @@
 use std::{
-    fs::File,
-    io::{BufRead, BufReader},
+    fs::{self, File},
+    io::{self, BufRead, BufReader},
 };
 
-pub fn fetch_history_commands(history_index: usize) -> Option<String> {
-    let file = File::open("history.txt");
-    match file {
-        Ok(file) => {
-            let reader = BufReader::new(file);
-            let lines: Vec<String> = reader.lines().flatten().collect();
-            let index = lines.len().checked_sub(history_index)?;
-            return lines.get(index).cloned();
-        }
-        Err(_) => {
-            return None;
+use crate::utilities::redirect::handle_redirect_append;
+
+//Marks the lines of history.txt that carry on the entry before them, so a command typed over
+//several lines stays a single entry.
+const CONTINUATION: char = '\x1f';
+
+pub fn append_history_entry(entry: &str) -> io::Result<()> {
+    let entry = entry.replace('\n', &format!("\n{}", CONTINUATION));
+    handle_redirect_append("history.txt", format!("{}\n", entry).as_bytes())
+}
+
+//Adds the lines of a plain history file to history.txt, one entry per line. Blank lines are
+//skipped and a missing final newline is supplied, so later entries never run into the last one.
+pub fn import_history(contents: &str) -> io::Result<()> {
+    let lines: String = contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| format!("{}\n", line))
+        .collect();
+    handle_redirect_append("history.txt", lines.as_bytes())
+}
+
+pub fn read_history_entries() -> Vec<String> {
+    let Ok(file) = File::open("history.txt") else {
+        return Vec::new();
+    };
+    let mut entries: Vec<String> = Vec::new();
+    for line in BufReader::new(file).lines().map_while(Result::ok) {
+        match (line.strip_prefix(CONTINUATION), entries.last_mut()) {
+            (Some(rest), Some(entry)) => {
+                entry.push('\n');
+                entry.push_str(rest);
+            }
+            _ => entries.push(line),
         }
     }
+    entries
+}
+
+//history.txt as a plain history file, one line per line of every entry, like bash writes it.
+pub fn read_history_file() -> Option<String> {
+    let contents = fs::read_to_string("history.txt").ok()?;
+    Some(contents.replace(&format!("\n{}", CONTINUATION), "\n"))
+}
+
+pub fn fetch_history_commands(history_index: usize) -> Option<String> {
+    let entries = read_history_entries();
+    let index = entries.len().checked_sub(history_index)?;
+    entries.get(index).cloned()
 }
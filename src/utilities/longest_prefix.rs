@@ -14,5 +14,5 @@ pub fn longest_common_prefix(strings: &[String]) -> String {
             break;
         }
     }
-    return prefix;
+    prefix
 }
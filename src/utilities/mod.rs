@@ -1,5 +1,5 @@
 pub mod executable;
+pub mod exit_status;
 pub mod history;
-pub mod input_parser;
 pub mod longest_prefix;
 pub mod redirect;
@@ -1,65 +1,309 @@
 use std::{
-    fs::{File, OpenOptions},
-    io::Write,
+    env, fmt,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    os::fd::{AsRawFd, FromRawFd, IntoRawFd, RawFd},
+    os::unix::fs::OpenOptionsExt,
+    process,
     str::FromStr,
+    sync::atomic::{AtomicUsize, Ordering},
 };
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Redirect {
-    Stdout,
-    StdoutAppend,
-    Stderr,
-    StderrAppend,
+    //`N>file` and `N>>file`, stdout unless N is given.
+    Output(RawFd),
+    Append(RawFd),
+    //`N<file`, stdin unless N is given.
+    Input(RawFd),
+    //`N<>file`, opened for reading and writing and created if missing.
+    ReadWrite(RawFd),
+    //`N>&M` and `N<&M` make N a copy of M, and `N>&-` closes N. The target word holds M or `-`.
+    DuplicateOutput(RawFd),
+    DuplicateInput(RawFd),
+    //`&>file` and `&>>file` send both stdout and stderr to the file.
+    OutputAll,
+    AppendAll,
+    HereDoc,
+    HereDocStripTabs,
+    HereString,
+}
+
+impl Redirect {
+    //Here-documents and here-strings feed text to stdin instead of naming a file.
+    pub fn is_here_document(&self) -> bool {
+        matches!(
+            self,
+            Redirect::HereDoc | Redirect::HereDocStripTabs | Redirect::HereString
+        )
+    }
+
+    //The file descriptor the redirection replaces. `&>` replaces stderr as well.
+    pub fn fd(&self) -> RawFd {
+        match self {
+            Redirect::OutputAll | Redirect::AppendAll => 1,
+            Redirect::Output(fd)
+            | Redirect::Append(fd)
+            | Redirect::Input(fd)
+            | Redirect::ReadWrite(fd)
+            | Redirect::DuplicateOutput(fd)
+            | Redirect::DuplicateInput(fd) => *fd,
+            Redirect::HereDoc | Redirect::HereDocStripTabs | Redirect::HereString => 0,
+        }
+    }
 }
 
 impl FromStr for Redirect {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        //These work on any fd, given as the number in front of the operator.
+        //Longer operators first, so `2>>` is not read as `2>` followed by `>`.
+        for (operator, default) in [
+            ("<>", 0),
+            (">&", 1),
+            ("<&", 0),
+            (">>", 1),
+            ("<", 0),
+            (">", 1),
+        ] {
+            let fd = match s.strip_suffix(operator) {
+                Some("") => default,
+                Some(number) if number.bytes().all(|b| b.is_ascii_digit()) => {
+                    number.parse().map_err(|_| ())?
+                }
+                _ => continue,
+            };
+            return Ok(match operator {
+                "<>" => Redirect::ReadWrite(fd),
+                ">&" => Redirect::DuplicateOutput(fd),
+                "<&" => Redirect::DuplicateInput(fd),
+                ">>" => Redirect::Append(fd),
+                ">" => Redirect::Output(fd),
+                _ => Redirect::Input(fd),
+            });
+        }
         match s {
-            ">" | "1>" => Ok(Redirect::Stdout),
-            ">>" | "1>>" => Ok(Redirect::StdoutAppend),
-            "2>" => Ok(Redirect::Stderr),
-            "2>>" => Ok(Redirect::StderrAppend),
+            "<<" => Ok(Redirect::HereDoc),
+            "<<-" => Ok(Redirect::HereDocStripTabs),
+            "<<<" => Ok(Redirect::HereString),
+            "&>" => Ok(Redirect::OutputAll),
+            "&>>" => Ok(Redirect::AppendAll),
             _ => Err(()),
         }
     }
 }
 
-pub fn get_filepath(args: &[String]) -> Option<&String> {
-    let position = args.iter().position(|s| s.parse::<Redirect>().is_ok());
-    let filepath = if let Some(position) = position {
-        args.get(position + 1)
-    } else {
-        None
-    };
-    return filepath;
+impl fmt::Display for Redirect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let default_fd = match self {
+            Redirect::Output(_) | Redirect::Append(_) | Redirect::DuplicateOutput(_) => 1,
+            _ => 0,
+        };
+        if let Redirect::Output(fd)
+        | Redirect::Append(fd)
+        | Redirect::Input(fd)
+        | Redirect::ReadWrite(fd)
+        | Redirect::DuplicateOutput(fd)
+        | Redirect::DuplicateInput(fd) = self
+            && *fd != default_fd
+        {
+            write!(f, "{}", fd)?;
+        }
+        let symbol = match self {
+            Redirect::Output(_) => ">",
+            Redirect::Append(_) => ">>",
+            Redirect::HereDoc => "<<",
+            Redirect::HereDocStripTabs => "<<-",
+            Redirect::HereString => "<<<",
+            Redirect::Input(_) => "<",
+            Redirect::ReadWrite(_) => "<>",
+            Redirect::DuplicateOutput(_) => ">&",
+            Redirect::DuplicateInput(_) => "<&",
+            Redirect::OutputAll => "&>",
+            Redirect::AppendAll => "&>>",
+        };
+        write!(f, "{}", symbol)
+    }
 }
 
-pub fn handle_redirect(filepath: &String, filecontent: &[u8]) {
-    let file = File::create(filepath);
-    match file {
-        Ok(mut file) => {
-            file.write_all(filecontent.trim_ascii()).unwrap();
-            file.write_all(b"\n").unwrap();
-        }
-        Err(_) => {
-            println!("cant create file");
+//A redirection whose target word has been turned into a path. For here-documents that is a
+//temporary file holding the text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedirectTarget {
+    pub kind: Redirect,
+    pub filepath: String,
+}
+
+//Writes here-document text to a fresh temporary file, which the command then reads as stdin.
+//The file must not exist yet and only the user can read it, so nobody else can plant a symlink
+//at the name or read the text.
+pub fn write_here_document(content: &str) -> io::Result<String> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    loop {
+        let name = format!(
+            "myshell-heredoc-{}-{}",
+            process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        );
+        let path = env::temp_dir().join(name).to_string_lossy().into_owned();
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&path);
+        match file {
+            Ok(mut file) => {
+                if let Err(err) = file.write_all(content.as_bytes()) {
+                    let _ = fs::remove_file(&path);
+                    return Err(err);
+                }
+                return Ok(path);
+            }
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err),
         }
     }
 }
 
-pub fn handle_redirect_append(filepath: &String, filecontent: &[u8]) {
-    let file = OpenOptions::new().create(true).append(true).open(filepath);
-    match file {
-        Ok(mut file) => {
-            file.write_all(filecontent.trim_ascii()).unwrap();
-            if !filecontent.is_empty() {
-                file.write_all(b"\n").unwrap();
+pub fn remove_here_documents(redirections: &[RedirectTarget]) {
+    for redirection in redirections.iter().filter(|r| r.kind.is_here_document()) {
+        let _ = fs::remove_file(&redirection.filepath);
+    }
+}
+
+//The fds a redirection replaced, put back by `restore`. A copy of -1 means the fd was closed.
+pub struct SavedFds {
+    saved: Vec<(RawFd, RawFd)>,
+}
+
+impl SavedFds {
+    pub fn restore(self) {
+        let _ = io::stdout().flush();
+        let _ = io::stderr().flush();
+        for (fd, copy) in self.saved.into_iter().rev() {
+            unsafe {
+                if copy == -1 {
+                    libc::close(fd);
+                } else {
+                    libc::dup2(copy, fd);
+                    libc::close(copy);
+                }
             }
         }
-        Err(_) => {
-            println!("cant create file");
+    }
+
+    //Keeps a copy of `fd` the first time a redirection is about to replace it.
+    fn save(&mut self, fd: RawFd) -> io::Result<()> {
+        if self.saved.iter().any(|(saved_fd, _)| *saved_fd == fd) {
+            return Ok(());
+        }
+        let _ = io::stdout().flush();
+        let _ = io::stderr().flush();
+        //Kept above the standard fds and closed on exec, so commands never see the copy.
+        let copy = unsafe { libc::fcntl(fd, libc::F_DUPFD_CLOEXEC, 10) };
+        if copy == -1 {
+            let err = io::Error::last_os_error();
+            if err.raw_os_error() != Some(libc::EBADF) {
+                return Err(err);
+            }
         }
+        self.saved.push((fd, copy));
+        Ok(())
     }
+
+    fn apply(&mut self, redirection: &RedirectTarget) -> io::Result<()> {
+        let fd = redirection.kind.fd();
+        let closes = matches!(
+            redirection.kind,
+            Redirect::DuplicateOutput(_) | Redirect::DuplicateInput(_)
+        ) && redirection.filepath == "-";
+        if closes {
+            self.save(fd)?;
+            unsafe { libc::close(fd) };
+            return Ok(());
+        }
+        let fds: &[RawFd] = match redirection.kind {
+            Redirect::OutputAll | Redirect::AppendAll => &[1, 2],
+            _ => &[fd],
+        };
+        for &fd in fds {
+            self.save(fd)?;
+        }
+        let file = open_target(redirection)?;
+        for &fd in fds {
+            if file.as_raw_fd() == fd {
+                //With `3<file` and fd 3 closed, the file was opened as fd 3 itself, but close on
+                //exec, which dup2 would have cleared.
+                unsafe { libc::fcntl(fd, libc::F_SETFD, 0) };
+            } else {
+                unsafe { libc::dup2(file.as_raw_fd(), fd) };
+            }
+        }
+        if fds.contains(&file.as_raw_fd()) {
+            let _ = file.into_raw_fd();
+        }
+        Ok(())
+    }
+}
+
+//Points the shell's own fds at the redirection targets, left to right, so everything run until
+//`restore` reads and writes there. `2>&1 >file` and `>file 2>&1` differ just like in bash.
+pub fn redirect_fds(redirections: &[RedirectTarget]) -> io::Result<SavedFds> {
+    let mut saved = SavedFds { saved: Vec::new() };
+    for redirection in redirections {
+        if let Err(err) = saved.apply(redirection) {
+            saved.restore();
+            return Err(err);
+        }
+    }
+    Ok(saved)
+}
+
+//Opens the file behind a redirection the way its operator asks for, naming it in any error.
+pub fn open_target(redirection: &RedirectTarget) -> io::Result<File> {
+    let filepath = &redirection.filepath;
+    let file = match redirection.kind {
+        Redirect::Output(_) | Redirect::OutputAll => File::create(filepath),
+        Redirect::Append(_) | Redirect::AppendAll => open_append(filepath),
+        Redirect::DuplicateOutput(_) | Redirect::DuplicateInput(_) => duplicate_fd(filepath),
+        Redirect::Input(_)
+        | Redirect::HereDoc
+        | Redirect::HereDocStripTabs
+        | Redirect::HereString => File::open(filepath),
+        Redirect::ReadWrite(_) => OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(filepath),
+    };
+    file.map_err(|err| io::Error::new(err.kind(), format!("{}: {}", filepath, err)))
+}
+
+//A fresh copy of the fd named by the target of `>&` or `<&`.
+pub fn duplicate_fd(target: &str) -> io::Result<File> {
+    let Ok(fd) = target.parse::<RawFd>() else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "ambiguous redirect",
+        ));
+    };
+    let copy = unsafe { libc::fcntl(fd, libc::F_DUPFD_CLOEXEC, 10) };
+    if copy == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(unsafe { File::from_raw_fd(copy) })
+}
+
+fn open_append(filepath: &str) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(filepath)
+}
+
+pub fn handle_redirect(filepath: &str, filecontent: &[u8]) -> io::Result<()> {
+    File::create(filepath)?.write_all(filecontent)
+}
+
+pub fn handle_redirect_append(filepath: &str, filecontent: &[u8]) -> io::Result<()> {
+    open_append(filepath)?.write_all(filecontent)
 }
@@ -0,0 +1,57 @@
+use std::fs;
+use std::io::Read;
+use std::os::unix::process::CommandExt;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+//Runs `script` with the shell and returns its stdout, killing it if it takes longer than `limit`.
+fn run_script(name: &str, script: &str, limit: Duration) -> Option<String> {
+    let path =
+        std::env::temp_dir().join(format!("myshell-test-{}-{}.sh", std::process::id(), name));
+    fs::write(&path, script).unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_codecrafters-shell"))
+        .arg(&path)
+        .stdout(Stdio::piped())
+        .process_group(0)
+        .spawn()
+        .unwrap();
+    let started = Instant::now();
+    let finished = loop {
+        if child.try_wait().unwrap().is_some() {
+            break true;
+        }
+        if started.elapsed() > limit {
+            //The whole group, since stages left running would keep stdout open.
+            unsafe { libc::kill(-(child.id() as i32), libc::SIGKILL) };
+            let _ = child.wait();
+            break false;
+        }
+        thread::sleep(Duration::from_millis(20));
+    };
+    let _ = fs::remove_file(&path);
+    if !finished {
+        return None;
+    }
+    let mut output = String::new();
+    child
+        .stdout
+        .take()
+        .unwrap()
+        .read_to_string(&mut output)
+        .unwrap();
+    Some(output)
+}
+
+#[test]
+fn writer_stops_when_reader_exits() {
+    let output = run_script("yes", "yes | head -n 1\n", Duration::from_secs(10));
+    assert_eq!(output.as_deref(), Some("y\n"));
+}
+
+#[test]
+fn builtin_writer_stops_when_reader_exits() {
+    let script = "while true; do echo y; done | head -n 1\n";
+    let output = run_script("loop", script, Duration::from_secs(10));
+    assert_eq!(output.as_deref(), Some("y\n"));
+}